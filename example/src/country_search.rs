@@ -1,55 +1,127 @@
-//! Loads country data from the [celes](https://crates.io/crates/celes) crate with each of the ISO 3166-1 ways of referring to a country forming keys in a [ternary search tree](https://crates.io/crates/tst), allowing for prefix searches
-pub use celes::Country;
-use std::collections::BTreeSet;
-use tst::TSTMap;
+//! Loads country data from the [celes](https://crates.io/crates/celes) crate and searches each of the ISO 3166-1 ways of referring to a country,
+//! matching each word of the query to the start of a word, in any order, so that eg. "kingdom united" and "cote" find "United Kingdom" and "Côte d'Ivoire".
+//! A code the query is exactly ranks highest, then names, then aliases.
+use seed_autocomplete::{IndexKey, SearchIndex, SuggestionItem};
+use std::ops::{Deref, Range};
+
+/// Which way of referring to a country matched a search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CountryField {
+    Code,
+    Alpha2,
+    Alpha3,
+    Name,
+    Alias,
+}
+
+impl CountryField {
+    pub fn label(self) -> &'static str {
+        match self {
+            CountryField::Code => "code",
+            CountryField::Alpha2 => "2 letter code",
+            CountryField::Alpha3 => "3 letter code",
+            CountryField::Name => "name",
+            CountryField::Alias => "alias",
+        }
+    }
+
+    /// Codes are short, so matching one exactly says more than matching the start of a name, but matching the start of one says less
+    fn weight(self) -> u32 {
+        match self {
+            CountryField::Code | CountryField::Alpha2 | CountryField::Alpha3 => 2,
+            CountryField::Name => 3,
+            CountryField::Alias => 1,
+        }
+    }
+}
+
+/// A country as a suggestion, celes' country is wrapped so that it can implement `SuggestionItem`
+#[derive(Debug, Clone)]
+pub struct Country {
+    pub country: celes::Country,
+    /// When it was found by something other than its name, which field matched and its text, eg. `(Alias, "Great Britain")`
+    pub matched: Option<(CountryField, String)>,
+}
+
+impl Deref for Country {
+    type Target = celes::Country;
+
+    fn deref(&self) -> &celes::Country {
+        &self.country
+    }
+}
+
+impl SuggestionItem for Country {
+    fn display_text(&self) -> String {
+        self.long_name.to_owned()
+    }
+
+    fn key(&self) -> String {
+        self.alpha3.to_owned()
+    }
+
+    /// All the ways of referring to the country
+    fn search_text(&self) -> String {
+        [self.code, self.alpha2, self.alpha3, self.long_name]
+            .iter()
+            .chain(self.aliases.iter())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
 
 pub struct CountrySearch {
-    /// prefixes for all the countries' different representations, pointing to their index
-    prefixes: TSTMap<Vec<usize>>,
-    entries: Vec<celes::Country>,
+    index: SearchIndex<celes::Country, CountryField>,
 }
 
 impl Default for CountrySearch {
     fn default() -> Self {
-        let entries = celes::Country::get_countries();
-        let mut prefixes = TSTMap::new();
-
-        for (
-            idx,
-            celes::Country {
-                code,
-                value: _,
-                alpha2,
-                alpha3,
-                long_name,
-                aliases,
-            },
-        ) in entries.iter().cloned().enumerate()
-        {
-            for key in [code, alpha2, alpha3, long_name]
-                .iter()
-                .chain(aliases.iter())
-            {
-                prefixes
-                    .entry(&key.to_lowercase())
-                    .or_insert(vec![])
-                    .push(idx);
-            }
+        let mut countries = celes::Country::get_countries();
+        // countries matching equally well stay in this order
+        countries.sort_by(|a, b| a.long_name.cmp(&b.long_name));
+        let mut index = SearchIndex::new();
+        for country in countries {
+            let keys = [
+                (CountryField::Code, country.code),
+                (CountryField::Alpha2, country.alpha2),
+                (CountryField::Alpha3, country.alpha3),
+                (CountryField::Name, country.long_name),
+            ]
+            .iter()
+            .copied()
+            .chain(
+                country
+                    .aliases
+                    .iter()
+                    .map(|alias| (CountryField::Alias, *alias)),
+            )
+            .map(|(field, text)| IndexKey::new(field, field.weight(), text))
+            .collect::<Vec<_>>();
+            index.insert(country, keys);
         }
-
-        Self { entries, prefixes }
+        Self { index }
     }
 }
 
 impl CountrySearch {
-    pub fn prefix_lookup(&self, prefix: &str) -> Vec<Country> {
-        self.prefixes
-            .prefix_iter(&prefix.to_lowercase())
-            .flat_map(|(_k, v)| v)
-            .cloned()
-            .collect::<BTreeSet<_>>()
+    /// The countries with a name, alias or code matching each word of the query, best matches first, with the ranges of the name which matched
+    pub fn search(&self, query: &str) -> Vec<(Country, Vec<Range<usize>>)> {
+        self.index
+            .search(query)
             .into_iter()
-            .map(|idx| self.entries[idx].clone())
+            .map(|result| {
+                // only the name is shown, so only its matches are highlighted
+                let (matched, ranges) = match result.field {
+                    CountryField::Name => (None, result.match_ranges),
+                    field => (Some((field, result.key.to_owned())), vec![]),
+                };
+                let country = Country {
+                    country: result.item.clone(),
+                    matched,
+                };
+                (country, ranges)
+            })
             .collect()
     }
 }
@@ -9,15 +9,13 @@ use tst::TSTSet;
 mod country_search;
 
 struct Model {
-    // Search autocomplete, records your previous submissions
-    /// Model for the autocomplete component
+    // Search autocomplete, records your previous submissions in local storage
+    /// Model for the autocomplete component, the data source for looking up suggestions is its history
     search_autocomplete: autocomplete::Model<Msg>,
-    /// data source for looking up suggestions
-    search_previous: TSTSet,
     search: Option<String>,
     search_input_value: String,
 
-    // Weekday autocomplete, allows chososing a weekday from prepopulated list
+    // Weekday autocomplete, allows chososing a weekday from prepopulated list like a searchable select, receives all its events through one message and shows all the days in order when empty
     /// Model for the autocomplete component
     weekday_autocomplete: autocomplete::Model<Msg>,
     /// data source for looking up suggestions, here the data is locally stored, you could instead fetch from a web service
@@ -28,8 +26,18 @@ struct Model {
     country_autocomplete: autocomplete::Model<Msg, Country>,
     /// data source for looking up suggestions, here the data is locally stored, you could instead fetch from a web service
     country_search: CountrySearch,
-    country_selected: Option<celes::Country>,
+    country_selected: Option<Country>,
+    /// country currently highlighted in the suggestions, previewed while the user moves through them
+    country_highlighted: Option<Country>,
     country_input_value: String,
+
+    /// Model for the autocomplete component, which suggests each of a comma separated list of countries
+    visit_autocomplete: autocomplete::Model<Msg, Country>,
+    /// the countries that have been entered so far
+    visit_values: Vec<String>,
+
+    /// Model for the autocomplete component, which suggests countries to mention in a comment after typing `@`
+    comment_autocomplete: autocomplete::Model<Msg, Country>,
 }
 
 #[derive(Clone)]
@@ -45,10 +53,8 @@ enum Msg {
 
     /// Wraps messages addressed to the autocomplete component
     WeekdayAutocomplete(autocomplete::Msg),
-    /// Autocomplete notifies us that the search contents have changed so we can update the suggestions
-    WeekdayInputChange(String),
-    /// Autocomplete notifies us that the user has made a selection
-    WeekdaySelected(String),
+    /// Autocomplete notifies us of something that happened, such as the contents changing or a selection being made
+    WeekdayEvent(autocomplete::Event<String>),
 
     /// Wraps messages addressed to the autocomplete component
     CountryAutocomplete(autocomplete::Msg),
@@ -56,6 +62,18 @@ enum Msg {
     CountryInputChange(String),
     /// Autocomplete notifies us that the user has made a selection
     CountrySelected,
+    /// Autocomplete notifies us that the highlighted suggestion changed
+    CountryHighlighted(Option<Country>),
+
+    /// Wraps messages addressed to the autocomplete component
+    VisitAutocomplete(autocomplete::Msg),
+    /// Autocomplete notifies us that the country at the caret has changed so we can update the suggestions
+    VisitInputChange(String),
+
+    /// Wraps messages addressed to the autocomplete component
+    CommentAutocomplete(autocomplete::Msg),
+    /// Autocomplete notifies us that the text after the `@` has changed so we can update the suggestions
+    CommentMentionChange(String),
 }
 
 fn init(_: Url, _orders: &mut impl Orders<Msg>) -> Model {
@@ -63,63 +81,102 @@ fn init(_: Url, _orders: &mut impl Orders<Msg>) -> Model {
         search_autocomplete: autocomplete::Model::new(Msg::SearchAutocomplete)
             .on_input_change(|s| Some(Msg::SearchInputChange(s.to_owned())))
             .on_selection(|s: &String| Some(Msg::SearchSelected(s.to_owned())))
-            .on_submit(|| Some(Msg::SearchSubmitted)),
-        search_previous: TSTSet::new(),
+            .on_submit(|| Some(Msg::SearchSubmitted))
+            .with_history(autocomplete::History::new(20).persisted(
+                "seed-autocomplete-example-search",
+                autocomplete::LocalHistoryStorage,
+            )),
         search: None,
         search_input_value: "".to_owned(),
 
         weekday_autocomplete: autocomplete::Model::new(Msg::WeekdayAutocomplete)
-            .on_input_change(|s| Some(Msg::WeekdayInputChange(s.to_owned())))
-            .on_selection(|s: &String| Some(Msg::WeekdaySelected(s.to_owned()))),
+            .on_event(Msg::WeekdayEvent)
+            .with_select_mode(true)
+            .with_default_suggestions(
+                [
+                    "monday",
+                    "tuesday",
+                    "wednesday",
+                    "thursday",
+                    "friday",
+                    "saturday",
+                    "sunday",
+                ]
+                .iter()
+                .map(|&day| day.to_owned())
+                .collect(),
+            ),
         weekday_search: tst::tstset! { "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"},
         weekday_selected: None,
 
         country_autocomplete: autocomplete::Model::new(Msg::CountryAutocomplete)
             .on_input_change(|s| Some(Msg::CountryInputChange(s.to_owned())))
-            .on_selection(|_| Some(Msg::CountrySelected)),
+            .on_selection(|_| Some(Msg::CountrySelected))
+            .on_highlight(|country| Some(Msg::CountryHighlighted(country.cloned())))
+            .with_escape_clears(true),
         country_search: CountrySearch::default(),
         country_selected: None,
+        country_highlighted: None,
         country_input_value: "".to_owned(),
+
+        visit_autocomplete: autocomplete::Model::new(Msg::VisitAutocomplete)
+            .on_input_change(|s| Some(Msg::VisitInputChange(s.to_owned())))
+            .with_delimiter(','),
+        visit_values: vec![],
+
+        comment_autocomplete: autocomplete::Model::new(Msg::CommentAutocomplete)
+            .on_input_change(|s| Some(Msg::CommentMentionChange(s.to_owned())))
+            .with_trigger('@'),
     }
 }
 
 fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::SearchInputChange(value) => {
-            let suggestions = model.search_previous.prefix_iter(&value);
-            model
-                .search_autocomplete
-                .set_suggestions(suggestions.collect());
+            if !value.is_empty() {
+                let suggestions = model
+                    .search_autocomplete
+                    .history()
+                    .into_iter()
+                    .flat_map(autocomplete::History::entries)
+                    .filter(|previous| previous.starts_with(&value))
+                    .cloned()
+                    .collect();
+                model.search_autocomplete.set_suggestions(suggestions);
+            }
             model.search_input_value = value;
         }
         Msg::SearchSubmitted => {
             let value = model.search_input_value.clone();
-            model.search_previous.insert(&value);
-            model.search_input_value = value.clone();
+            if !value.is_empty() {
+                model.search_autocomplete.record_history(value.clone());
+            }
             model.search = Some(value);
         }
         Msg::SearchSelected(value) => {
-            assert!(model.search_previous.contains(&value));
             model.search_input_value = value.clone();
             model.search = Some(value);
         }
         Msg::SearchAutocomplete(msg) => model.search_autocomplete.update(msg, orders),
 
-        Msg::WeekdayInputChange(value) => {
+        Msg::WeekdayEvent(autocomplete::Event::InputChanged(value)) if !value.is_empty() => {
             let suggestions = model.weekday_search.prefix_iter(&value);
             model
                 .weekday_autocomplete
                 .set_suggestions(suggestions.collect());
         }
-        Msg::WeekdaySelected(value) => {
+        Msg::WeekdayEvent(autocomplete::Event::Selected(value)) => {
             model.weekday_selected = Some(value);
         }
+        Msg::WeekdayEvent(_) => {}
         Msg::WeekdayAutocomplete(msg) => model.weekday_autocomplete.update(msg, orders),
 
         Msg::CountryInputChange(value) => {
             if !value.is_empty() {
-                let suggestions = model.country_search.prefix_lookup(&value);
-                model.country_autocomplete.set_suggestions(suggestions);
+                let suggestions = model.country_search.search(&value);
+                model
+                    .country_autocomplete
+                    .set_suggestions_with_matches(suggestions);
             }
             model.country_input_value = value;
         }
@@ -130,7 +187,31 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 model.country_selected = Some(selection);
             }
         }
+        Msg::CountryHighlighted(country) => {
+            model.country_highlighted = country;
+        }
         Msg::CountryAutocomplete(msg) => model.country_autocomplete.update(msg, orders),
+
+        Msg::VisitInputChange(value) => {
+            if !value.is_empty() {
+                let suggestions = model.country_search.search(&value);
+                model
+                    .visit_autocomplete
+                    .set_suggestions_with_matches(suggestions);
+            }
+        }
+        Msg::VisitAutocomplete(msg) => {
+            model.visit_autocomplete.update(msg, orders);
+            model.visit_values = model.visit_autocomplete.values();
+        }
+
+        Msg::CommentMentionChange(value) => {
+            let suggestions = model.country_search.search(&value);
+            model
+                .comment_autocomplete
+                .set_suggestions_with_matches(suggestions);
+        }
+        Msg::CommentAutocomplete(msg) => model.comment_autocomplete.update(msg, orders),
     }
 }
 
@@ -168,42 +249,103 @@ fn view(model: &Model) -> Vec<Node<Msg>> {
             div![
                 "Search for a country name, alias or ISO 3166-1 code:",
                 // the view for the autocomplete box, adding it into the vdom
-                model.country_autocomplete.view()
+                model
+                    .country_autocomplete
+                    .view()
+                    .with_input_attrs(attrs! {
+                        At::Type => "search",
+                        At::Value => &model.country_input_value,
+                    })
+                    .with_clear_button()
+                    .with_input_view(|input| span!["🌍 ", input])
+                    .with_suggestion_view(|suggestion, context| {
+                        div![
+                            style! {
+                                St::Background => if context.is_highlighted { "lightgray" } else { "white" },
+                                St::Cursor => "default",
+                            },
+                            if context.is_selected { "✓ " } else { "" },
+                            autocomplete::highlight_matches(&suggestion.long_name, context.match_ranges),
+                            suggestion.matched.as_ref().map(|(field, key)| {
+                                small![format!(" matched {}: {}", field.label(), key)]
+                            }),
+                            span![
+                                style! {
+                                    St::Float => context.direction.inline_end(),
+                                },
+                                format!("{}, {}", suggestion.alpha2, suggestion.alpha3),
+                            ]
+                        ]
+                    })
+                    .into_nodes(),
+            ],
+            model
+                .country_highlighted
+                .as_ref()
+                .map(|highlighted_country| div![
+                    em!["Preview:"],
+                    country_details(highlighted_country)
+                ]),
+            model.country_selected.as_ref().map(country_details),
+        ],
+        section![
+            div![
+                "Countries to visit, separated by commas:",
+                // the view for the autocomplete box, adding it into the vdom
+                model
+                    .visit_autocomplete
+                    .view()
+                    .with_suggestion_view(|country, context| {
+                        div![
+                            style! {
+                                St::Background => if context.is_highlighted { "lightgray" } else { "white" },
+                                St::Cursor => "default",
+                            },
+                            autocomplete::highlight_matches(&country.long_name, context.match_ranges),
+                        ]
+                    })
+                    .into_nodes(),
+            ],
+            ul![model.visit_values.iter().map(|value| li![value])],
+        ],
+        section![div![
+            "Comment, mention a country by typing @:",
+            // the view for the autocomplete box, adding it into the vdom
+            model
+                .comment_autocomplete
+                .view()
+                .with_textarea()
                 .with_input_attrs(attrs! {
-                    At::Type => "search",
-                    At::Value => &model.country_input_value,
-                }).with_suggestion_view(|suggestion, is_highlighted| {
+                    At::Rows => "4",
+                    At::Cols => "50",
+                })
+                .with_suggestion_view(|country, context| {
                     div![
                         style! {
-                            St::Background => if is_highlighted { "lightgray" } else { "white" },
+                            St::Background => if context.is_highlighted { "lightgray" } else { "white" },
                             St::Cursor => "default",
                         },
-                        suggestion.long_name.clone(),
-                        span![
-                            style! {
-                                St::Float => "right",
-                            },
-                            format!("{}, {}", suggestion.alpha2, suggestion.alpha3),
-                        ]
+                        autocomplete::highlight_matches(&country.long_name, context.match_ranges),
                     ]
-                }).into_nodes(),
+                })
+                .into_nodes(),
+        ]],
+    ]
+}
+
+fn country_details(country: &Country) -> Node<Msg> {
+    div![
+        h3![&country.long_name],
+        ul![
+            li!["Country code:", &country.code],
+            li!["2 letter code:", &country.alpha2],
+            li!["3 letter code:", &country.alpha3],
+            li!["Long name:", &country.long_name],
+            li![
+                "Aliases:",
+                ul![country.aliases.iter().map(|alias| li![alias])]
             ],
-            model.country_selected.as_ref().map(|selected_country| {
-                div![
-                    h3![&selected_country.long_name],
-                    ul![
-                        li!["Country code:", &selected_country.code],
-                        li!["2 letter code:", &selected_country.alpha2],
-                        li!["3 letter code:", &selected_country.alpha3],
-                        li!["Long name:", &selected_country.long_name],
-                        li![
-                            "Aliases:",
-                            ul![selected_country.aliases.iter().map(|alias| li![alias])]
-                        ],
-                    ],
-                ]
-            }),
-        ]
+        ],
     ]
 }
 
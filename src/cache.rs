@@ -0,0 +1,279 @@
+//! Remembers the suggestions found for recent queries, so that they don't have to be fetched again
+use super::{matching, SuggestionItem};
+use std::collections::VecDeque;
+
+/// Suggestions found for a query
+struct Entry<Suggestion> {
+    /// The normalized query
+    query: String,
+    suggestions: Vec<Suggestion>,
+    /// Whether these are all the suggestions for the query, rather than eg. the first page
+    is_complete: bool,
+    /// When the entry was added, in milliseconds since the epoch
+    added_at: f64,
+}
+
+/// Suggestions found in the cache
+#[derive(Debug, Clone)]
+pub struct CacheHit<Suggestion> {
+    pub suggestions: Vec<Suggestion>,
+    /// Whether the suggestions should still be fetched, to replace these if they have changed
+    pub needs_revalidation: bool,
+}
+
+/// The suggestions for the most recently used queries, limited to a maximum number of queries with the least recently used being dropped first.
+/// Queries are compared ignoring surrounding whitespace, case and diacritics, see `matching::normalize`.
+pub struct SuggestionCache<Suggestion> {
+    /// Most recently used first
+    entries: VecDeque<Entry<Suggestion>>,
+    capacity: usize,
+    /// How long entries are used for, in milliseconds
+    ttl: Option<f64>,
+    revalidate: bool,
+    /// Whether a suggestion matches a query, for narrowing down the suggestions of a shorter query
+    matches: Option<fn(&Suggestion, &str) -> bool>,
+}
+
+impl<Suggestion: Clone> SuggestionCache<Suggestion> {
+    /// Create an empty cache which will keep the suggestions of at most `capacity` queries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            ttl: None,
+            revalidate: false,
+            matches: None,
+        }
+    }
+
+    /// Stop using entries `ttl_ms` milliseconds after they were added
+    pub fn with_ttl(mut self, ttl_ms: f64) -> Self {
+        self.ttl = Some(ttl_ms);
+        self
+    }
+
+    /// When enabled, cached suggestions are shown straight away but still fetched again, in case they have changed
+    pub fn with_revalidation(mut self, revalidate: bool) -> Self {
+        self.revalidate = revalidate;
+        self
+    }
+
+    /// When a query extends a cached query whose suggestions were complete, find its suggestions by filtering those with `matches`,
+    /// which should give the same result as the source of the suggestions, eg. `|country, query| matching::normalize(&country.name).starts_with(query)`.
+    /// The query passed to `matches` is normalized, see `matching::normalize`.
+    pub fn with_narrowing(mut self, matches: fn(&Suggestion, &str) -> bool) -> Self {
+        self.matches = Some(matches);
+        self
+    }
+
+    /// Narrow down cached suggestions to those with a word of their search text starting with each word of the query, in any order and ignoring case and diacritics,
+    /// see `matching::token_match` and `with_narrowing`. This suits suggestions which are found by matching their search text in the same way.
+    pub fn with_prefix_narrowing(self) -> Self
+    where
+        Suggestion: SuggestionItem,
+    {
+        self.with_narrowing(|suggestion, query| {
+            matching::token_match(&suggestion.search_text(), query).is_some()
+        })
+    }
+
+    /// The suggestions for `query`, either cached for it or narrowed down from those of a shorter query.
+    /// `now` is the time in milliseconds since the epoch, eg. `js_sys::Date::now()`.
+    pub fn get(&mut self, query: &str, now: f64) -> Option<CacheHit<Suggestion>> {
+        let query = normalize(query);
+        if let Some(ttl) = self.ttl {
+            self.entries.retain(|entry| now - entry.added_at < ttl);
+        }
+        let suggestions =
+            if let Some(idx) = self.entries.iter().position(|entry| entry.query == query) {
+                let entry = self.entries.remove(idx)?;
+                let suggestions = entry.suggestions.clone();
+                self.entries.push_front(entry);
+                suggestions
+            } else {
+                let matches = self.matches?;
+                // the longest query with complete suggestions, as that has the fewest to filter
+                let entry = self
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.is_complete && query.starts_with(&entry.query))
+                    .max_by_key(|entry| entry.query.len())?;
+                let suggestions: Vec<_> = entry
+                    .suggestions
+                    .iter()
+                    .filter(|suggestion| matches(suggestion, &query))
+                    .cloned()
+                    .collect();
+                let added_at = entry.added_at;
+                self.push(Entry {
+                    query,
+                    suggestions: suggestions.clone(),
+                    is_complete: true,
+                    added_at,
+                });
+                suggestions
+            };
+        Some(CacheHit {
+            suggestions,
+            needs_revalidation: self.revalidate,
+        })
+    }
+
+    /// Remember the suggestions for `query`, `is_complete` should be false if there are more suggestions than these, eg. when they are the first page.
+    /// `now` is the time in milliseconds since the epoch, as for `get`.
+    pub fn insert(
+        &mut self,
+        query: &str,
+        suggestions: Vec<Suggestion>,
+        is_complete: bool,
+        now: f64,
+    ) {
+        let query = normalize(query);
+        self.entries.retain(|entry| entry.query != query);
+        self.push(Entry {
+            query,
+            suggestions,
+            is_complete,
+            added_at: now,
+        });
+    }
+
+    /// Forget all the entries, eg. when the source of the suggestions has changed
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn push(&mut self, entry: Entry<Suggestion>) {
+        self.entries.push_front(entry);
+        self.entries.truncate(self.capacity);
+    }
+}
+
+/// Queries which differ only in surrounding whitespace, case or diacritics have the same suggestions
+pub(crate) fn normalize(query: &str) -> String {
+    matching::normalize(query.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn cached(cache: &mut SuggestionCache<String>, query: &str, now: f64) -> Option<Vec<String>> {
+        cache.get(query, now).map(|hit| hit.suggestions)
+    }
+
+    #[test]
+    fn queries_are_normalized() {
+        let mut cache = SuggestionCache::<String>::new(2);
+        cache.insert("Zoë", strings(&["zoë"]), true, 0.0);
+        assert_eq!(cached(&mut cache, " zoe ", 0.0), Some(strings(&["zoë"])));
+    }
+
+    #[test]
+    fn entries_expire() {
+        let mut cache = SuggestionCache::<String>::new(2).with_ttl(100.0);
+        cache.insert("a", strings(&["ab"]), true, 0.0);
+        assert_eq!(cached(&mut cache, "a", 99.0), Some(strings(&["ab"])));
+        assert_eq!(cached(&mut cache, "a", 100.0), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_is_dropped() {
+        let mut cache = SuggestionCache::<String>::new(2);
+        cache.insert("a", strings(&["a"]), true, 0.0);
+        cache.insert("b", strings(&["b"]), true, 0.0);
+        cache.insert("c", strings(&["c"]), true, 0.0);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cached(&mut cache, "a", 0.0), None);
+        assert!(cached(&mut cache, "b", 0.0).is_some());
+        assert!(cached(&mut cache, "c", 0.0).is_some());
+    }
+
+    #[test]
+    fn hits_move_to_the_front() {
+        let mut cache = SuggestionCache::<String>::new(2);
+        cache.insert("a", strings(&["a"]), true, 0.0);
+        cache.insert("b", strings(&["b"]), true, 0.0);
+        // "a" is now the most recently used, so "b" is dropped for "c"
+        assert!(cached(&mut cache, "a", 0.0).is_some());
+        cache.insert("c", strings(&["c"]), true, 0.0);
+        assert!(cached(&mut cache, "a", 0.0).is_some());
+        assert_eq!(cached(&mut cache, "b", 0.0), None);
+    }
+
+    #[test]
+    fn narrowing_uses_the_longest_complete_prefix() {
+        let mut cache = SuggestionCache::<String>::new(4)
+            .with_narrowing(|suggestion, query| suggestion.starts_with(query));
+        cache.insert("a", strings(&["abc", "abcd", "ax"]), true, 0.0);
+        cache.insert("ab", strings(&["abc"]), true, 0.0);
+        cache.insert("abc", strings(&["abc"]), false, 0.0);
+        // "abc" isn't complete, so "abcd" is narrowed down from "ab" rather than "a"
+        assert_eq!(cached(&mut cache, "abcd", 0.0), Some(strings(&[])));
+        assert_eq!(cached(&mut cache, "ax", 0.0), Some(strings(&["ax"])));
+    }
+
+    #[test]
+    fn prefix_narrowing_matches_each_word() {
+        let mut cache = SuggestionCache::<String>::new(4).with_prefix_narrowing();
+        cache.insert(
+            "u",
+            strings(&["United Kingdom GB", "Uruguay UY", "Ukraine UA"]),
+            true,
+            0.0,
+        );
+        assert_eq!(
+            cached(&mut cache, "u kingd", 0.0),
+            Some(strings(&["United Kingdom GB"]))
+        );
+        assert_eq!(
+            cached(&mut cache, "uy", 0.0),
+            Some(strings(&["Uruguay UY"]))
+        );
+        assert_eq!(
+            cached(&mut cache, "Ukra", 0.0),
+            Some(strings(&["Ukraine UA"]))
+        );
+    }
+
+    #[test]
+    fn narrowed_entries_expire_with_their_source() {
+        let mut cache = SuggestionCache::<String>::new(4)
+            .with_ttl(100.0)
+            .with_narrowing(|suggestion, query| suggestion.starts_with(query));
+        cache.insert("a", strings(&["ab", "ac"]), true, 0.0);
+        assert_eq!(cached(&mut cache, "ab", 50.0), Some(strings(&["ab"])));
+        assert_eq!(cached(&mut cache, "ab", 100.0), None);
+    }
+
+    #[test]
+    fn no_narrowing_from_incomplete_suggestions() {
+        let mut cache = SuggestionCache::<String>::new(2)
+            .with_narrowing(|suggestion, query| suggestion.starts_with(query));
+        cache.insert("a", strings(&["ab"]), false, 0.0);
+        assert_eq!(cached(&mut cache, "ab", 0.0), None);
+        let mut cache = SuggestionCache::<String>::new(2);
+        cache.insert("a", strings(&["ab"]), true, 0.0);
+        assert_eq!(cached(&mut cache, "ab", 0.0), None);
+    }
+
+    #[test]
+    fn revalidation_is_reported() {
+        let mut cache = SuggestionCache::<String>::new(2).with_revalidation(true);
+        cache.insert("a", strings(&["a"]), true, 0.0);
+        assert!(cache.get("a", 0.0).unwrap().needs_revalidation);
+    }
+}
@@ -0,0 +1,279 @@
+//! Records the suggestions a user has previously chosen, so that they can be offered again
+use super::{SuggestionGroup, SuggestionItem};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Somewhere to persist the history between page loads
+pub trait HistoryStorage {
+    fn load(&self, key: &str) -> Option<String>;
+
+    fn save(&self, key: &str, value: &str);
+}
+
+/// Persists history in the browser's `localStorage`, failures to access the storage are ignored and the history will just not survive a reload
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalHistoryStorage;
+
+impl HistoryStorage for LocalHistoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        local_storage()?.get_item(key).ok().flatten()
+    }
+
+    fn save(&self, key: &str, value: &str) {
+        if let Some(storage) = local_storage() {
+            let _ = storage.set_item(key, value);
+        }
+    }
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+/// Keeps history in memory, clones share the same entries so one can be kept to inspect what was saved
+#[derive(Debug, Clone, Default)]
+pub struct MemoryHistoryStorage {
+    values: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl HistoryStorage for MemoryHistoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.values.borrow().get(key).cloned()
+    }
+
+    fn save(&self, key: &str, value: &str) {
+        self.values
+            .borrow_mut()
+            .insert(key.to_owned(), value.to_owned());
+    }
+}
+
+/// Where and how the history is saved
+struct Persistence<Suggestion> {
+    key: String,
+    storage: Box<dyn HistoryStorage>,
+    serialize: fn(&Suggestion) -> String,
+}
+
+/// The most recently chosen suggestions, without duplicates, and limited to a maximum number of entries with the least recently chosen being dropped first.
+/// Entries with the same `SuggestionItem::key` are duplicates.
+pub struct History<Suggestion> {
+    /// Most recent first
+    entries: VecDeque<Suggestion>,
+    capacity: usize,
+    persistence: Option<Persistence<Suggestion>>,
+}
+
+impl<Suggestion: SuggestionItem> History<Suggestion> {
+    /// Create an empty history which will keep at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            persistence: None,
+        }
+    }
+
+    /// Record that `entry` was chosen, moving it to the front if it was already in the history
+    pub fn record(&mut self, entry: Suggestion) {
+        let key = entry.key();
+        self.entries.retain(|existing| existing.key() != key);
+        self.entries.push_front(entry);
+        self.entries.truncate(self.capacity);
+        self.save();
+    }
+}
+
+impl<Suggestion: ToString + FromStr> History<Suggestion> {
+    /// Load the history saved in `storage` under `key` and save it there whenever it changes. Use a different key for each autocomplete.
+    /// Entries which no longer parse are dropped.
+    pub fn persisted(
+        mut self,
+        key: impl Into<String>,
+        storage: impl HistoryStorage + 'static,
+    ) -> Self {
+        let key = key.into();
+        if let Some(saved) = storage.load(&key) {
+            self.entries = decode(&saved)
+                .filter_map(|entry| entry.parse().ok())
+                .take(self.capacity)
+                .collect();
+        }
+        self.persistence = Some(Persistence {
+            key,
+            storage: Box::new(storage),
+            serialize: ToString::to_string,
+        });
+        self
+    }
+}
+
+impl<Suggestion> History<Suggestion> {
+    /// Forget all the entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    /// The entries, most recent first
+    pub fn entries(&self) -> impl Iterator<Item = &Suggestion> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries as a group of suggestions for the menu
+    pub fn group(&self, label: impl Into<String>) -> SuggestionGroup<Suggestion>
+    where
+        Suggestion: Clone,
+    {
+        SuggestionGroup {
+            label: label.into(),
+            suggestions: self.entries.iter().cloned().collect(),
+        }
+    }
+
+    fn save(&self) {
+        if let Some(Persistence {
+            key,
+            storage,
+            serialize,
+        }) = &self.persistence
+        {
+            storage.save(key, &encode(self.entries.iter().map(serialize)));
+        }
+    }
+}
+
+/// Entries are saved one per line, with backslashes and newlines escaped
+fn encode(entries: impl Iterator<Item = String>) -> String {
+    entries
+        .map(|entry| entry.replace('\\', "\\\\").replace('\n', "\\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode(saved: &str) -> impl Iterator<Item = String> + '_ {
+    saved.lines().map(|line| {
+        let mut entry = String::with_capacity(line.len());
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                entry.push(match chars.next() {
+                    Some('n') => '\n',
+                    Some(escaped) => escaped,
+                    None => '\\',
+                });
+            } else {
+                entry.push(c);
+            }
+        }
+        entry
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding_round_trips_escapes() {
+        let entries = vec![
+            "plain".to_owned(),
+            "two\nlines".to_owned(),
+            "back\\slash".to_owned(),
+            "escaped \\n newline".to_owned(),
+            "trailing\\".to_owned(),
+        ];
+        let saved = encode(entries.iter().cloned());
+        assert_eq!(saved.lines().count(), entries.len());
+        assert_eq!(decode(&saved).collect::<Vec<_>>(), entries);
+    }
+
+    #[test]
+    fn record_moves_duplicates_to_the_front() {
+        let mut history = History::new(3);
+        history.record("a".to_owned());
+        history.record("b".to_owned());
+        history.record("a".to_owned());
+        assert_eq!(history.entries().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Entry {
+        id: u32,
+        label: &'static str,
+    }
+
+    impl SuggestionItem for Entry {
+        fn display_text(&self) -> String {
+            self.label.to_owned()
+        }
+
+        fn key(&self) -> String {
+            self.id.to_string()
+        }
+    }
+
+    #[test]
+    fn record_compares_keys() {
+        let mut history = History::new(3);
+        history.record(Entry {
+            id: 1,
+            label: "old",
+        });
+        history.record(Entry {
+            id: 2,
+            label: "old",
+        });
+        history.record(Entry {
+            id: 1,
+            label: "new",
+        });
+        assert_eq!(
+            history.entries().collect::<Vec<_>>(),
+            [
+                &Entry {
+                    id: 1,
+                    label: "new"
+                },
+                &Entry {
+                    id: 2,
+                    label: "old"
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn record_drops_least_recent_beyond_capacity() {
+        let mut history = History::new(2);
+        history.record("a".to_owned());
+        history.record("b".to_owned());
+        history.record("a".to_owned());
+        history.record("c".to_owned());
+        assert_eq!(history.entries().collect::<Vec<_>>(), ["c", "a"]);
+    }
+
+    #[test]
+    fn persisted_history_is_reloaded() {
+        let storage = MemoryHistoryStorage::default();
+        let mut history = History::new(2).persisted("search", storage.clone());
+        history.record("one\nline".to_owned());
+        history.record("two".to_owned());
+        history.record("three".to_owned());
+
+        let reloaded: History<String> = History::new(2).persisted("search", storage.clone());
+        assert_eq!(reloaded.entries().collect::<Vec<_>>(), ["three", "two"]);
+        let elsewhere: History<String> = History::new(2).persisted("other", storage);
+        assert!(elsewhere.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+//! Searching items by several fields of text, eg. a name, aliases and codes, where matches of some fields count for more than others
+use super::matching::{self, NormalizedText};
+use std::ops::Range;
+
+/// Text an item can be found by
+#[derive(Debug, Clone)]
+pub struct IndexKey<Field> {
+    /// Which of the item's fields the text is, reported when it matches
+    pub field: Field,
+    /// How much a match of this key counts, relative to other keys
+    pub weight: u32,
+    pub text: String,
+}
+
+impl<Field> IndexKey<Field> {
+    pub fn new(field: Field, weight: u32, text: impl Into<String>) -> Self {
+        Self {
+            field,
+            weight,
+            text: text.into(),
+        }
+    }
+}
+
+/// An item found by a search, with the key which matched it best
+#[derive(Debug, Clone)]
+pub struct SearchResult<'a, Item, Field> {
+    pub item: &'a Item,
+    /// Which of the item's fields matched
+    pub field: Field,
+    /// The text of the key which matched
+    pub key: &'a str,
+    /// Byte ranges of `key` which matched the query, for highlighting
+    pub match_ranges: Vec<Range<usize>>,
+    /// Higher is better, results are ordered by this
+    pub score: u32,
+}
+
+/// Extra score for a key which is the whole query, before it is weighted
+const EXACT_SCORE: u32 = 50;
+
+/// Items which can be searched by any of their keys, matching each word of the query to a word of a key, see `matching::token_match`.
+/// The score of a match is weighted by its key's weight, so that eg. an exact match of a code ranks above a match of a name, which ranks above a match of an alias.
+pub struct SearchIndex<Item, Field> {
+    items: Vec<Item>,
+    /// With the index of their item and their normalized text, so that the keys aren't normalized again for every search
+    keys: Vec<(usize, IndexKey<Field>, NormalizedText)>,
+}
+
+impl<Item, Field> Default for SearchIndex<Item, Field> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl<Item, Field: Copy> SearchIndex<Item, Field> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `item`, to be found by any of `keys`
+    pub fn insert(&mut self, item: Item, keys: impl IntoIterator<Item = IndexKey<Field>>) {
+        let item_idx = self.items.len();
+        self.items.push(item);
+        self.keys.extend(keys.into_iter().map(|key| {
+            let normalized = NormalizedText::new(&key.text);
+            (item_idx, key, normalized)
+        }));
+    }
+
+    /// The items matching `query`, each with its best matching key, best first. Items with the same score stay in the order they were added.
+    pub fn search(&self, query: &str) -> Vec<SearchResult<'_, Item, Field>> {
+        let query = matching::normalize(query);
+        let mut best: Vec<Option<SearchResult<'_, Item, Field>>> =
+            self.items.iter().map(|_| None).collect();
+        for (item_idx, key, normalized) in &self.keys {
+            let token_match = match matching::normalized_token_match(normalized, &query) {
+                Some(token_match) => token_match,
+                None => continue,
+            };
+            let exact_score = if normalized.as_str() == query.trim() {
+                EXACT_SCORE
+            } else {
+                0
+            };
+            let score = (token_match.score + exact_score).saturating_mul(key.weight);
+            let is_better = match &best[*item_idx] {
+                Some(result) => score > result.score,
+                None => true,
+            };
+            if is_better {
+                best[*item_idx] = Some(SearchResult {
+                    item: &self.items[*item_idx],
+                    field: key.field,
+                    key: &key.text,
+                    match_ranges: token_match.ranges,
+                    score,
+                });
+            }
+        }
+        let mut results: Vec<_> = best.into_iter().flatten().collect();
+        results.sort_by_key(|result| std::cmp::Reverse(result.score));
+        results
+    }
+
+    /// All the items, in the order they were added
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Field {
+        Code,
+        Name,
+        Alias,
+    }
+
+    fn index() -> SearchIndex<&'static str, Field> {
+        let mut index = SearchIndex::new();
+        for (code, name, alias) in &[
+            ("GB", "United Kingdom", "Great Britain"),
+            ("GR", "Greece", "Hellas"),
+            ("DE", "Germany", "Deutschland"),
+        ] {
+            index.insert(
+                *name,
+                vec![
+                    IndexKey::new(Field::Code, 3, *code),
+                    IndexKey::new(Field::Name, 2, *name),
+                    IndexKey::new(Field::Alias, 1, *alias),
+                ],
+            );
+        }
+        index
+    }
+
+    fn found(results: &[SearchResult<'_, &'static str, Field>]) -> Vec<(&'static str, Field)> {
+        results
+            .iter()
+            .map(|result| (*result.item, result.field))
+            .collect()
+    }
+
+    #[test]
+    fn heavier_fields_rank_higher() {
+        // "Greece" and "Great Britain" match equally well, but the name weighs more
+        let index = index();
+        let results = index.search("gre");
+        assert_eq!(
+            found(&results),
+            [("Greece", Field::Name), ("United Kingdom", Field::Alias)]
+        );
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn exact_matches_rank_higher() {
+        let index = index();
+        let exact = index.search("gb");
+        assert_eq!(found(&exact), [("United Kingdom", Field::Code)]);
+        let results = index.search("g");
+        // "GB" and "GR" are matched equally well by "g", and come before the names they outweigh
+        assert_eq!(
+            found(&results),
+            [
+                ("United Kingdom", Field::Code),
+                ("Greece", Field::Code),
+                ("Germany", Field::Name),
+            ]
+        );
+        assert!(exact[0].score > results[0].score);
+    }
+
+    #[test]
+    fn best_key_is_reported() {
+        let index = index();
+        let results = index.search("britain great");
+        assert_eq!(found(&results), [("United Kingdom", Field::Alias)]);
+        assert_eq!(results[0].key, "Great Britain");
+        assert_eq!(results[0].match_ranges, [0..5, 6..13]);
+    }
+
+    #[test]
+    fn ties_keep_insertion_order() {
+        let mut index = SearchIndex::new();
+        for name in &["Beta", "Alpha", "Gamma"] {
+            index.insert(*name, vec![IndexKey::new((), 1, "same")]);
+        }
+        let results = index.search("same");
+        let items: Vec<_> = results.iter().map(|result| *result.item).collect();
+        assert_eq!(items, ["Beta", "Alpha", "Gamma"]);
+    }
+
+    #[test]
+    fn large_weights_saturate() {
+        let mut index = SearchIndex::new();
+        index.insert("item", vec![IndexKey::new((), u32::MAX, "key")]);
+        assert_eq!(index.search("key")[0].score, u32::MAX);
+    }
+}
@@ -0,0 +1,35 @@
+//! How the component shows, compares and searches suggestions
+
+/// A suggestion which can be shown in the autocomplete. Implement this once for your suggestion type,
+/// for a type from another crate wrap it in a type of your own.
+pub trait SuggestionItem {
+    /// The text shown for the suggestion by the default view, and written into the input for it when a selection completes part of the text
+    fn display_text(&self) -> String;
+
+    /// Identifies the suggestion, suggestions with the same key are the same, eg. when finding the selection among the suggestions
+    fn key(&self) -> String {
+        self.display_text()
+    }
+
+    /// The text a query is matched against, which can include more than the display text, eg. aliases
+    fn search_text(&self) -> String {
+        self.display_text()
+    }
+
+    /// Whether the suggestion is shown but can't be highlighted or selected
+    fn is_disabled(&self) -> bool {
+        false
+    }
+}
+
+impl SuggestionItem for String {
+    fn display_text(&self) -> String {
+        self.clone()
+    }
+}
+
+impl SuggestionItem for &str {
+    fn display_text(&self) -> String {
+        (*self).to_owned()
+    }
+}
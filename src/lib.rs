@@ -1,9 +1,25 @@
+use query::{QueryMode, Token};
 use seed::prelude::*;
 use seed::*;
-use web_sys::HtmlInputElement;
+use std::ops::Range;
+use web_sys::HtmlElement;
 
+mod cache;
+mod history;
+mod index;
+mod item;
+pub mod matching;
+mod messages;
+mod query;
+mod text_field;
 mod view_builder;
-pub use view_builder::{ViewBuilder, ViewBuilderDefault};
+pub use cache::{CacheHit, SuggestionCache};
+pub use history::{History, HistoryStorage, LocalHistoryStorage, MemoryHistoryStorage};
+pub use index::{IndexKey, SearchIndex, SearchResult};
+pub use item::SuggestionItem;
+pub use messages::Messages;
+use view_builder::ViewOptions;
+pub use view_builder::{ClassNames, ViewBuilder, ViewBuilderDefault, DEFAULT_STYLESHEET};
 
 #[derive(Debug, Clone)]
 pub enum Msg {
@@ -15,6 +31,142 @@ pub enum Msg {
     SuggestionClick(usize),
     SuggestionHover(usize),
     SetIgnoreSuggestionBlur(bool),
+    ClearClick,
+    ToggleClick,
+    TypeAheadTimeout,
+    MenuScrolledToEnd,
+    /// Show the children of the suggestion at this index
+    ExpandClick(usize),
+    /// Go back up to the level of the breadcrumb at this depth
+    BreadcrumbClick(usize),
+}
+
+/// Events emitted by the autocomplete component, an alternative to registering a callback for each kind of event.
+/// Set a mapper for these with `Model::on_event` and match on them in your update function.
+#[derive(Debug, Clone)]
+pub enum Event<Suggestion> {
+    /// The value of the input changed
+    InputChanged(String),
+    /// Suggestions are needed for the query, which the cache couldn't provide or which should be revalidated
+    Fetch(String),
+    /// A suggestion was selected from the menu
+    Selected(Suggestion),
+    /// The input was submitted, either by pressing enter or by selecting a suggestion. Selecting a suggestion after a trigger or delimiter doesn't submit.
+    Submitted,
+    /// The suggestion menu was opened
+    Opened,
+    /// The suggestion menu was closed
+    Closed,
+    /// The highlighted suggestion changed, contains the index of the newly highlighted suggestion
+    HighlightChanged(Option<usize>),
+    /// The input was cleared
+    Cleared,
+    /// More suggestions are wanted, the menu was scrolled to the end or the last suggestion highlighted, see `Model::set_has_more`.
+    /// Fetch the next page and add it with `Model::append_suggestions`.
+    LoadMore,
+    /// Something went wrong, but the component recovered
+    Error(Error),
+}
+
+/// Problems which the component recovered from, reported with `Model::on_error`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The input element was needed but isn't in the DOM
+    MissingInput,
+    /// A suggestion was referred to which doesn't exist, eg. the suggestions changed while one was highlighted
+    SuggestionOutOfRange { index: usize, len: usize },
+    /// A call to the DOM failed
+    Dom(String),
+}
+
+/// A group of suggestions shown together in the menu under a header
+#[derive(Debug, Clone)]
+pub struct SuggestionGroup<Suggestion> {
+    pub label: String,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// The direction text is laid out in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// The CSS side where text starts, for `float` or positioning
+    pub fn inline_start(self) -> &'static str {
+        match self {
+            Direction::Ltr => "left",
+            Direction::Rtl => "right",
+        }
+    }
+
+    /// The CSS side where text ends, for `float` or positioning
+    pub fn inline_end(self) -> &'static str {
+        match self {
+            Direction::Ltr => "right",
+            Direction::Rtl => "left",
+        }
+    }
+
+    /// The value of the `dir` attribute
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+}
+
+/// Where to place the menu in the viewport, when it shouldn't just go below the input
+#[derive(Debug, Clone, Copy)]
+struct MenuPosition {
+    top: f64,
+    /// The distance from the side of the viewport where text starts, the left or for right to left text the right
+    inline_start: f64,
+}
+
+/// Everything a suggestion view might need to know about the suggestion it is rendering
+#[derive(Debug, Clone)]
+pub struct SuggestionContext<'a> {
+    /// Position of the suggestion in the menu
+    pub index: usize,
+    pub is_highlighted: bool,
+    /// Whether the suggestion is the current selection, the suggestions are compared with `SuggestionItem::key`
+    pub is_selected: bool,
+    /// Whether the suggestion can't be selected, see `SuggestionItem::is_disabled`
+    pub is_disabled: bool,
+    /// Whether the suggestion has children which can be drilled into, see `Model::with_children`
+    pub has_children: bool,
+    /// The direction of the component's text, eg. for floating something to the end of the suggestion
+    pub direction: Direction,
+    /// The text the user has typed
+    pub query: &'a str,
+    /// Byte ranges of the suggestion's text which match the query, if they were provided with `Model::set_suggestions_with_matches`
+    pub match_ranges: &'a [Range<usize>],
+    /// The group the suggestion is shown in, if it is in one
+    pub group: Option<GroupContext<'a>>,
+}
+
+/// Where a suggestion is within its group
+#[derive(Debug, Clone)]
+pub struct GroupContext<'a> {
+    pub label: &'a str,
+    /// Position of the suggestion within the group
+    pub index: usize,
+    /// Number of suggestions in the group
+    pub len: usize,
+}
+
+/// The suggestions shown before drilling into the children of one of them
+struct Level<Suggestion> {
+    /// The suggestion which was drilled into, and its index in `suggestions`
+    parent: Suggestion,
+    index: usize,
+    suggestions: Vec<Suggestion>,
+    groups: Vec<(Range<usize>, String)>,
+    match_ranges: Vec<Vec<Range<usize>>>,
 }
 
 /// Model of the autocomplete component, one of these is needed in your model for each autocomplete in the view
@@ -26,13 +178,68 @@ pub struct Model<Ms, Suggestion = String> {
     input_changed: Box<dyn Fn(&str) -> Option<Ms>>,
     suggestion_selected: Box<dyn Fn(&Suggestion) -> Option<Ms>>,
     submit: Box<dyn Fn() -> Option<Ms>>,
+    highlight_changed: Box<dyn Fn(Option<&Suggestion>) -> Option<Ms>>,
+    open_changed: Box<dyn Fn(bool) -> Option<Ms>>,
+    error: Box<dyn Fn(&Error) -> Option<Ms>>,
+    /// Maps events to the parent message type, if the parent wants to receive them
+    event_mapper: Option<fn(Event<Suggestion>) -> Ms>,
 
-    input_ref: ElRef<HtmlInputElement>,
+    /// Provides the suggestions shown when the input is focused or emptied, if this isn't set the menu shows whatever suggestions were last set
+    default_suggestions: Option<Box<dyn Fn() -> Vec<Suggestion>>>,
+    /// Suggestions for recent queries, used instead of fetching them again
+    cache: Option<SuggestionCache<Suggestion>>,
+    /// Previous selections, shown as a group when the input is empty
+    history: Option<History<Suggestion>>,
+    /// Whether pressing escape when the menu is already closed clears the input
+    escape_clears: bool,
+    /// Whether the input behaves like a searchable `select`, see `with_select_mode`
+    select_mode: bool,
+    /// Whether the select stays read-only while open, with typing moving the highlight, see `with_type_ahead`
+    type_ahead: bool,
+    /// How the query is found in the text of the input
+    query_mode: QueryMode,
+    /// Text shown or announced by the component
+    messages: Messages,
+    /// The direction set with `with_direction`, otherwise it is taken from the input's style
+    direction: Option<Direction>,
+    /// The children of a suggestion, or `None` if it can't be drilled into
+    children: Option<Box<dyn Fn(&Suggestion) -> Option<Vec<Suggestion>>>>,
+    /// Whether a suggestion has children, without building them, see `with_has_children`
+    expandable: Option<Box<dyn Fn(&Suggestion) -> bool>>,
+
+    /// The `input` or `textarea` element
+    input_ref: ElRef<HtmlElement>,
+    /// The part of the input's text being completed, if there is one at the caret
+    token: Option<Token>,
+    /// The query as last typed by the user, in the default mode this is the value of the input
+    query: String,
     selected: Option<Suggestion>,
     suggestions: Vec<Suggestion>,
+    /// The ranges of `suggestions` which are grouped under a header, with the header's label
+    groups: Vec<(Range<usize>, String)>,
+    /// The parts of each suggestion which match the query, either empty or the same length as `suggestions`
+    match_ranges: Vec<Vec<Range<usize>>>,
+    /// The characters typed recently in type-ahead mode, lower case
+    type_ahead_buffer: String,
+    /// Empties the buffer when the user pauses typing, dropping this cancels it
+    type_ahead_timeout: Option<CmdHandle>,
+    /// The levels above the suggestions shown, from the top, when they are the children of another suggestion
+    path: Vec<Level<Suggestion>>,
+    /// Whether there are more suggestions than those shown, which can be loaded
+    has_more: bool,
+    /// Whether more suggestions have been asked for, but not appended yet
+    loading_more: bool,
 
     /// Whether the component is open
     is_open: bool,
+    /// Whether a selection was made since the menu was last opened, so that it is announced
+    announce_selection: bool,
+    /// Whether suggestions were set since the query last changed, so that their number is announced
+    announce_results: bool,
+    /// The direction of the input's text, found when the menu opens
+    input_direction: Direction,
+    /// Where to place the menu in the viewport, when it shouldn't just go below the input
+    menu_position: Option<MenuPosition>,
     /// If an element is highlighted, this referes to its index in the `suggestions` vector
     highlighted_index: Option<usize>,
     /// Ignore any blur events. This flag is set when hovering over the suggestions. When the suggestion menu is open, the input box must have focus, a click on a suggestion will cause a blur event on the input, closing the menu, before the click event on the suggestion.
@@ -41,18 +248,46 @@ pub struct Model<Ms, Suggestion = String> {
     ignore_focus: bool,
 }
 
-impl<Ms: 'static, Suggestion: Clone> Model<Ms, Suggestion> {
+impl<Ms: 'static, Suggestion: Clone + SuggestionItem> Model<Ms, Suggestion> {
     pub fn new(msg_mapper: fn(Msg) -> Ms) -> Self {
         Self {
             msg_mapper,
             input_changed: Box::new(|_| None),
             suggestion_selected: Box::new(|_| None),
             submit: Box::new(|| None),
+            highlight_changed: Box::new(|_| None),
+            open_changed: Box::new(|_| None),
+            error: Box::new(|_| None),
+            event_mapper: None,
+            default_suggestions: None,
+            cache: None,
+            history: None,
+            escape_clears: false,
+            select_mode: false,
+            type_ahead: false,
+            query_mode: QueryMode::Whole,
+            messages: Messages::default(),
+            direction: None,
+            children: None,
+            expandable: None,
 
             input_ref: Default::default(),
+            token: Default::default(),
+            query: Default::default(),
             selected: Default::default(),
             suggestions: Default::default(),
+            groups: Default::default(),
+            match_ranges: Default::default(),
+            type_ahead_buffer: Default::default(),
+            type_ahead_timeout: Default::default(),
+            path: Default::default(),
+            has_more: Default::default(),
+            loading_more: Default::default(),
             is_open: Default::default(),
+            announce_selection: Default::default(),
+            announce_results: Default::default(),
+            input_direction: Direction::Ltr,
+            menu_position: Default::default(),
             highlighted_index: Default::default(),
             ignore_blur: Default::default(),
             ignore_focus: Default::default(),
@@ -77,20 +312,284 @@ impl<Ms: 'static, Suggestion: Clone> Model<Ms, Suggestion> {
         self
     }
 
+    /// Called whenever the highlighted suggestion changes, with `None` when nothing is highlighted any more
+    pub fn on_highlight(
+        mut self,
+        highlight_changed: impl Fn(Option<&Suggestion>) -> Option<Ms> + 'static,
+    ) -> Self {
+        self.highlight_changed = Box::new(highlight_changed);
+        self
+    }
+
+    /// Called whenever the suggestion menu opens (`true`) or closes (`false`)
+    pub fn on_open_change(mut self, open_changed: impl Fn(bool) -> Option<Ms> + 'static) -> Self {
+        self.open_changed = Box::new(open_changed);
+        self
+    }
+
+    /// Called when something goes wrong which the component can recover from, for logging or diagnostics
+    pub fn on_error(mut self, error: impl Fn(&Error) -> Option<Ms> + 'static) -> Self {
+        self.error = Box::new(error);
+        self
+    }
+
+    /// Receive every `Event` of the component as a message, this can be used instead of or alongside the `on_*` callbacks
+    pub fn on_event(mut self, event_mapper: fn(Event<Suggestion>) -> Ms) -> Self {
+        self.event_mapper = Some(event_mapper);
+        self
+    }
+
+    /// Show these suggestions when the input is empty, so that the user can pick one without typing, eg. favourites
+    pub fn with_default_suggestions(self, default_suggestions: Vec<Suggestion>) -> Self
+    where
+        Suggestion: 'static,
+    {
+        self.with_default_suggestions_provider(move || default_suggestions.clone())
+    }
+
+    /// Show the suggestions returned by `provider` when the input is empty, so that the user can pick one without typing, eg. recent selections
+    pub fn with_default_suggestions_provider(
+        mut self,
+        provider: impl Fn() -> Vec<Suggestion> + 'static,
+    ) -> Self {
+        self.default_suggestions = Some(Box::new(provider));
+        self
+    }
+
+    /// Show the cached suggestions for a query straight away instead of asking for them with `Event::Fetch`, add suggestions to the cache with `cache_suggestions`
+    pub fn with_cache(mut self, cache: SuggestionCache<Suggestion>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The cache of suggestions, if the autocomplete has one, eg. to clear it
+    pub fn cache_mut(&mut self) -> Option<&mut SuggestionCache<Suggestion>> {
+        self.cache.as_mut()
+    }
+
+    /// Add the fetched suggestions for `query` to the cache, and show them if `query` is still the current query.
+    /// `is_complete` should be false if there are more suggestions than these, eg. when they are the first page.
+    pub fn cache_suggestions(
+        &mut self,
+        query: &str,
+        suggestions: Vec<Suggestion>,
+        is_complete: bool,
+    ) {
+        if cache::normalize(query) == cache::normalize(&self.query) {
+            self.set_suggestions(suggestions.clone());
+        }
+        if let Some(cache) = &mut self.cache {
+            cache.insert(query, suggestions, is_complete, js_sys::Date::now());
+        }
+    }
+
+    /// Record selections in `history` and show them in a "Recent" group when the input is empty, see `Messages::recent`
+    pub fn with_history(mut self, history: History<Suggestion>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// The history of selections, if the autocomplete has one
+    pub fn history(&self) -> Option<&History<Suggestion>> {
+        self.history.as_ref()
+    }
+
+    /// Add an entry to the history, for example when the user submits text that isn't one of the suggestions
+    pub fn record_history(&mut self, entry: Suggestion) {
+        if let Some(history) = &mut self.history {
+            history.record(entry);
+        }
+    }
+
+    /// When enabled, pressing escape while the menu is closed clears the input, so a first escape closes the menu and a second clears
+    pub fn with_escape_clears(mut self, escape_clears: bool) -> Self {
+        self.escape_clears = escape_clears;
+        self
+    }
+
+    /// Behave like a searchable `select`: a toggle button beside the input opens the menu with all the default suggestions, see `with_default_suggestions`,
+    /// the display text of the selection is shown read-only while the menu is closed, and typing filters the suggestions while it is open.
+    /// Alt+ArrowDown opens the menu and Alt+ArrowUp selects the highlighted suggestion and closes it.
+    pub fn with_select_mode(mut self, select_mode: bool) -> Self {
+        self.select_mode = select_mode;
+        self
+    }
+
+    /// In select mode, keep the input read-only while the menu is open too, and instead of filtering,
+    /// typing highlights the first suggestion whose display text starts with the characters typed, as a native `select` does.
+    /// The characters are forgotten after a short pause, and typing the same character again moves on to the next suggestion starting with it.
+    pub fn with_type_ahead(mut self, type_ahead: bool) -> Self {
+        self.type_ahead = type_ahead;
+        self
+    }
+
+    /// Change the text the component shows or announces to screen readers, eg. to translate it
+    pub fn with_messages(mut self, messages: Messages) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Lay out the component in `direction`, setting its `dir` attribute, by default the direction is taken from the input's style, eg. the `dir` attribute of the page.
+    /// For right to left text the menu is aligned to the right edge of the input, and ArrowLeft drills into hierarchical suggestions while ArrowRight goes back.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Make suggestions hierarchical, eg. region → country → city. Suggestions for which `children` returns `Some` are shown as expandable with a › at their end,
+    /// pressing ArrowRight or clicking the › shows their children in place of the suggestions, and ArrowLeft or the breadcrumb at the top of the menu goes back.
+    /// Like any other suggestion, pressing enter or clicking elsewhere on one selects it.
+    /// The breadcrumb shows the display text of each parent. If finding the children is costly, also set `with_has_children`.
+    pub fn with_children(
+        mut self,
+        children: impl Fn(&Suggestion) -> Option<Vec<Suggestion>> + 'static,
+    ) -> Self {
+        self.children = Some(Box::new(children));
+        self
+    }
+
+    /// Say whether a suggestion has children without building them, for marking suggestions as expandable in the menu.
+    /// Without this, `children` is called for each suggestion shown every time the menu is rendered, see `with_children`.
+    pub fn with_has_children(
+        mut self,
+        has_children: impl Fn(&Suggestion) -> bool + 'static,
+    ) -> Self {
+        self.expandable = Some(Box::new(has_children));
+        self
+    }
+
+    /// Only suggest while the caret is in a word starting with `trigger`, eg. `@` for mentions or `#` for hashtags.
+    /// The query passed to `on_input_change` is the text between the trigger and the caret, the menu is shown at the caret,
+    /// and selecting a suggestion replaces the trigger and query with the suggestion's display text.
+    /// This is intended for use with `ViewBuilder::with_textarea`, where enter will then only select suggestions and not submit.
+    pub fn with_trigger(mut self, trigger: char) -> Self {
+        self.query_mode = QueryMode::Trigger(trigger);
+        self
+    }
+
+    /// Allow several values in the input separated by `delimiter`, eg. `,` for a list of email addresses.
+    /// The query passed to `on_input_change` is the value at the caret and selecting a suggestion replaces just that value with the suggestion's display text.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.query_mode = QueryMode::Delimited(delimiter);
+        self
+    }
+
+    /// The values in the input, which are separated by the delimiter if there is one, see `with_delimiter`
+    pub fn values(&self) -> Vec<String> {
+        self.query_mode
+            .values(&self.input_value().unwrap_or_default())
+    }
+
+    /// The text in the input, if it has been rendered
+    pub fn input_value(&self) -> Option<String> {
+        self.input_ref
+            .get()
+            .and_then(|input| text_field::value(&input))
+    }
+
     /// Get the last selected suggestion
     pub fn get_selection(&self) -> Option<&Suggestion> {
         self.selected.as_ref()
     }
 
-    /// Change the suggestions in the suggestion box
+    /// Change the suggestions in the suggestion box.
+    /// The highlight is cleared, unless it is on the selection and the selection is among the new suggestions, in which case it stays on it.
     pub fn set_suggestions(&mut self, suggestions: Vec<Suggestion>) {
+        let keep_selection = self.is_selection_highlighted();
         self.suggestions = suggestions;
+        self.groups.clear();
+        self.match_ranges.clear();
+        self.path.clear();
+        self.loading_more = false;
+        self.announce_results = true;
+        self.reset_highlight(keep_selection);
+    }
+
+    /// Add suggestions to the end of the menu, eg. the next page of results, keeping the highlighted suggestion.
+    /// If the last group reaches the end of the menu, the suggestions are added to it.
+    pub fn append_suggestions(&mut self, suggestions: Vec<Suggestion>) {
+        let end = self.suggestions.len();
+        self.suggestions.extend(suggestions);
+        if let Some((range, _)) = self.groups.last_mut().filter(|(range, _)| range.end == end) {
+            range.end = self.suggestions.len();
+        }
+        if !self.match_ranges.is_empty() {
+            self.match_ranges.resize(self.suggestions.len(), Vec::new());
+        }
+        self.loading_more = false;
     }
 
+    /// Set whether there are more suggestions than those set, in which case `Event::LoadMore` is sent when the user reaches the end of the menu,
+    /// by highlighting the last suggestion or scrolling near it. The menu only scrolls if its height is limited, as it is by default, so keep a `max-height` when styling it.
+    /// Set this after setting the suggestions.
+    pub fn set_has_more(&mut self, has_more: bool) {
+        self.has_more = has_more;
+        if !has_more {
+            self.loading_more = false;
+        }
+    }
+
+    /// Change the suggestions in the suggestion box, along with the byte ranges of each suggestion's text which matched the query, for highlighting in the view.
+    /// The highlight changes as for `set_suggestions`.
+    pub fn set_suggestions_with_matches(
+        &mut self,
+        suggestions: Vec<(Suggestion, Vec<Range<usize>>)>,
+    ) {
+        let (suggestions, match_ranges) = suggestions.into_iter().unzip();
+        self.set_suggestions(suggestions);
+        self.match_ranges = match_ranges;
+    }
+
+    /// Change the suggestions in the suggestion box, showing each group under a header. The highlight changes as for `set_suggestions`.
+    pub fn set_suggestion_groups(&mut self, groups: Vec<SuggestionGroup<Suggestion>>) {
+        let keep_selection = self.is_selection_highlighted();
+        self.suggestions.clear();
+        self.groups.clear();
+        self.match_ranges.clear();
+        self.path.clear();
+        self.loading_more = false;
+        for SuggestionGroup { label, suggestions } in groups {
+            let start = self.suggestions.len();
+            self.suggestions.extend(suggestions);
+            self.groups.push((start..self.suggestions.len(), label));
+        }
+        self.announce_results = true;
+        self.reset_highlight(keep_selection);
+    }
+
+    /// Handle a message of the component. This doesn't require the input to be in the DOM, eg. before a pre-rendered page is hydrated, in which case anything needing the element is skipped.
     pub fn update(&mut self, msg: Msg, orders: &mut impl Orders<Ms>) {
         match msg {
             Msg::InputChange(value) => {
-                (*self.input_changed)(&value).map(|msg| orders.send_msg(msg));
+                let input = self.input_ref.get();
+                let caret = input
+                    .as_ref()
+                    .and_then(|input| text_field::caret(input, &value))
+                    .unwrap_or(value.len());
+                self.token = self.query_mode.token(&value, caret);
+                let query = match &self.token {
+                    Some(token) => value[token.query.clone()].to_owned(),
+                    None => {
+                        self.query.clear();
+                        self.set_open(false, orders);
+                        return;
+                    }
+                };
+                if self.query_mode.is_triggered() {
+                    self.menu_position = input.as_ref().and_then(|input| {
+                        let (top, left) = text_field::caret_coordinates(input, caret)?;
+                        self.menu_position_at(input, top, left)
+                    });
+                    self.set_open(true, orders);
+                }
+                self.query = query.clone();
+                // the suggestions are for the old query until they are replaced
+                self.announce_results = false;
+                self.set_highlighted_index(None, orders);
+                (*self.input_changed)(&query).map(|msg| orders.send_msg(msg));
+                self.emit(Event::InputChanged(query), orders);
+                self.fetch_suggestions(orders);
+                self.show_default_suggestions(orders);
             }
 
             Msg::InputFocus => {
@@ -100,48 +599,102 @@ impl<Ms: 'static, Suggestion: Clone> Model<Ms, Suggestion> {
                     return;
                 }
                 // TODO handling for focus causing a scroll which could cause a click to be cancelled
-                self.is_open = true;
+                // a select is only opened by clicking or the keyboard
+                if !self.select_mode && (!self.query_mode.is_triggered() || self.token.is_some()) {
+                    self.show_default_suggestions(orders);
+                    self.set_open(true, orders);
+                }
             }
 
             Msg::InputBlur => {
                 if self.ignore_blur {
-                    self.ignore_focus = true;
                     // TODO is there scroll handling to do here?
-                    self.input_ref.get().unwrap().focus().unwrap();
-                    return;
+                    match self.input_ref.get().map(|input| input.focus()) {
+                        Some(Ok(())) => {
+                            self.ignore_focus = true;
+                            return;
+                        }
+                        Some(Err(err)) => self.report(Error::Dom(format!("{:?}", err)), orders),
+                        None => self.report(Error::MissingInput, orders),
+                    }
                 }
-                self.is_open = false;
-                self.highlighted_index = None;
+                self.set_open(false, orders);
+                self.set_highlighted_index(None, orders);
             }
 
             Msg::SetIgnoreSuggestionBlur(value) => self.ignore_blur = value,
 
             Msg::InputKeyDown(kb_ev) => {
-                match kb_ev.key().as_str() {
-                    "ArrowDown" => {
+                let key = kb_ev.key();
+                // the arrows for drilling in and out of suggestions point the way the text goes
+                let key = match (self.direction(), key.as_str()) {
+                    (Direction::Rtl, "ArrowLeft") => "ArrowRight",
+                    (Direction::Rtl, "ArrowRight") => "ArrowLeft",
+                    (_, key) => key,
+                };
+                match key {
+                    // after a trigger, the arrows only move the highlight while the menu is open
+                    "ArrowDown" | "ArrowUp" if !self.is_open && self.query_mode.is_triggered() => {}
+                    "ArrowDown" | "ArrowUp" if !self.is_open && self.select_mode => {
                         kb_ev.prevent_default();
-                        if self.suggestions.is_empty() {
-                            return;
+                        self.open_all(orders);
+                    }
+                    "ArrowDown" if kb_ev.alt_key() => {
+                        kb_ev.prevent_default();
+                        if !self.is_open {
+                            self.highlight_selection(orders);
+                        }
+                        self.set_open(true, orders);
+                    }
+                    "ArrowUp" if kb_ev.alt_key() => {
+                        kb_ev.prevent_default();
+                        match self.highlighted_index {
+                            Some(highlighted_index) if self.select_mode => {
+                                self.select(highlighted_index, orders)
+                            }
+                            _ => {
+                                self.set_highlighted_index(None, orders);
+                                self.set_open(false, orders);
+                            }
                         }
-                        let index = self.highlighted_index.map(|i| i + 1).unwrap_or(0);
-                        if index < self.suggestions.len() {
-                            self.highlighted_index = Some(index);
-                            self.is_open = true;
+                    }
+                    // opening the menu with the arrows starts from the current selection, so that the user can see it and move on from it
+                    "ArrowDown" | "ArrowUp" if !self.is_open && self.selected_index().is_some() => {
+                        kb_ev.prevent_default();
+                        self.highlight_selection(orders);
+                        self.set_open(true, orders);
+                    }
+                    "ArrowDown" => {
+                        kb_ev.prevent_default();
+                        let start = self.highlighted_index.map(|i| i + 1).unwrap_or(0);
+                        if let Some(index) =
+                            (start..self.suggestions.len()).find(|&idx| !self.is_disabled(idx))
+                        {
+                            self.set_highlighted_index(Some(index), orders);
+                            self.set_open(true, orders);
                         }
                     }
                     "ArrowUp" => {
                         kb_ev.prevent_default();
-                        if self.suggestions.is_empty() {
-                            return;
-                        }
-                        let index = self
+                        let end = self
                             .highlighted_index
                             .unwrap_or_else(|| self.suggestions.len());
-                        if index > 0 {
-                            self.highlighted_index = Some(index - 1);
-                            self.is_open = true;
+                        if let Some(index) = (0..end).rev().find(|&idx| !self.is_disabled(idx)) {
+                            self.set_highlighted_index(Some(index), orders);
+                            self.set_open(true, orders);
+                        }
+                    }
+                    "ArrowRight" if self.is_open => {
+                        if let Some(highlighted_index) = self.highlighted_index {
+                            if self.drill_down(highlighted_index, orders) {
+                                kb_ev.prevent_default();
+                            }
                         }
                     }
+                    "ArrowLeft" if self.is_open && !self.path.is_empty() => {
+                        kb_ev.prevent_default();
+                        self.go_up(self.path.len() - 1, orders);
+                    }
                     "Enter" => {
                         // Key code 229 is used for selecting items from character selectors (Pinyin, Kana, etc)
                         if kb_ev.key_code() != 13 {
@@ -151,62 +704,458 @@ impl<Ms: 'static, Suggestion: Clone> Model<Ms, Suggestion> {
                         self.ignore_blur = false;
                         if !self.is_open {
                             // menu is closed so there is no selection to accept -> do nothing
-                            (*self.submit)().map(|msg| orders.send_msg(msg));
+                            // in a textarea enter is a new line
+                            if !self.is_multiline() {
+                                self.submit(orders);
+                            }
                         } else if let Some(highlighted_index) = self.highlighted_index {
                             // text entered + menu item has been highlighted + enter is hit -> update value to that of selected menu item, close the menu
                             kb_ev.prevent_default();
-                            let item = &self.suggestions[highlighted_index];
-                            self.is_open = false;
-                            self.highlighted_index = None;
-                            self.selected = Some(item.clone());
-                            (*self.suggestion_selected)(&item).map(|msg| orders.send_msg(msg));
-                            (*self.submit)().map(|msg| orders.send_msg(msg));
+                            self.select(highlighted_index, orders);
                         } else {
-                            self.is_open = false;
-                            (*self.submit)().map(|msg| orders.send_msg(msg));
+                            self.set_open(false, orders);
+                            // with nothing highlighted in the menu of a mention or a value, enter goes on editing
+                            if self.query_mode == QueryMode::Whole && !self.is_multiline() {
+                                self.submit(orders);
+                            }
                         }
                     }
                     "Escape" => {
                         // In case the user is currently hovering over the menu
                         self.ignore_blur = false;
-                        self.highlighted_index = None;
-                        self.is_open = false;
+                        if !self.is_open && self.escape_clears {
+                            self.clear(orders);
+                        } else {
+                            self.set_highlighted_index(None, orders);
+                            self.set_open(false, orders);
+                        }
                     }
                     "Tab" => {
                         // In case the user is currently hovering over the menu
                         self.ignore_blur = false;
                     }
+                    key if self.select_mode
+                        && self.type_ahead
+                        && key.chars().count() == 1
+                        && !kb_ev.ctrl_key()
+                        && !kb_ev.meta_key() =>
+                    {
+                        kb_ev.prevent_default();
+                        if !self.is_open {
+                            self.open_all(orders);
+                        }
+                        self.type_ahead(key, orders);
+                    }
+                    // a closed select is read-only, so the first character typed is put in the input once it is opened
+                    key if self.select_mode && !self.is_open && key.chars().count() == 1 => {
+                        kb_ev.prevent_default();
+                        self.open_all(orders);
+                        if let Some(input) = self.input_ref.get() {
+                            text_field::set_value(&input, key);
+                        }
+                        self.update(Msg::InputChange(key.to_owned()), orders);
+                    }
+                    _ if self.select_mode => {}
                     _ => {
-                        self.is_open = true;
+                        // after a trigger, the menu is opened by the input changing
+                        if !self.query_mode.is_triggered() {
+                            self.set_open(true, orders);
+                        }
                     }
                 }
             }
 
             Msg::InputClick(_mouse_ev) => {
-                let element = self.input_ref.get().unwrap();
+                let element = match self.input_ref.get() {
+                    Some(element) => element,
+                    None => return self.report(Error::MissingInput, orders),
+                };
                 if element
                     .owner_document()
                     .and_then(|doc| doc.active_element())
-                    .map(|active_element| active_element == element.into())
+                    .map(|active_element| active_element == element.clone().into())
                     .unwrap_or_default()
                 {
-                    self.is_open = true;
+                    if self.select_mode {
+                        if !self.is_open {
+                            self.open_all(orders);
+                        }
+                    } else if self.query_mode == QueryMode::Whole {
+                        self.set_open(true, orders);
+                    } else if let Some(value) = text_field::value(&element) {
+                        // the caret may have moved into or out of a word being completed
+                        self.update(Msg::InputChange(value), orders);
+                    }
                 }
             }
 
             Msg::SuggestionHover(idx) => {
-                self.highlighted_index = Some(idx);
+                if !self.is_disabled(idx) {
+                    self.set_highlighted_index(Some(idx), orders);
+                }
             }
 
             Msg::SuggestionClick(idx) => {
-                let item = &self.suggestions[idx];
-                self.selected = Some(item.clone());
+                if self.is_disabled(idx) {
+                    return;
+                }
                 self.ignore_blur = false;
-                self.is_open = false;
-                self.highlighted_index = None;
-                (*self.suggestion_selected)(&item).map(|msg| orders.send_msg(msg));
-                (*self.submit)().map(|msg| orders.send_msg(msg));
+                self.select(idx, orders);
+            }
+
+            Msg::ExpandClick(idx) => {
+                if !self.is_disabled(idx) {
+                    self.drill_down(idx, orders);
+                }
+            }
+
+            Msg::BreadcrumbClick(depth) => self.go_up(depth, orders),
+
+            Msg::ClearClick => {
+                self.ignore_blur = false;
+                self.clear(orders);
+            }
+
+            Msg::ToggleClick => {
+                if self.is_open {
+                    self.set_highlighted_index(None, orders);
+                    self.set_open(false, orders);
+                    return;
+                }
+                match self.input_ref.get().map(|input| input.focus()) {
+                    Some(Ok(())) => {}
+                    Some(Err(err)) => self.report(Error::Dom(format!("{:?}", err)), orders),
+                    None => self.report(Error::MissingInput, orders),
+                }
+                self.open_all(orders);
+            }
+
+            Msg::TypeAheadTimeout => {
+                self.type_ahead_buffer.clear();
+                self.type_ahead_timeout = None;
             }
+
+            Msg::MenuScrolledToEnd => self.load_more(orders),
+        }
+    }
+
+    /// Empty the input, forget the selection and the suggestions and close the menu.
+    /// The parent is notified of the input change as well as the clearing, so that it can update any value it sets on the input.
+    pub fn clear(&mut self, orders: &mut impl Orders<Ms>) {
+        if let Some(input) = self.input_ref.get() {
+            text_field::set_value(&input, "");
+        }
+        self.token = None;
+        self.query.clear();
+        self.selected = None;
+        self.set_suggestions(Vec::new());
+        self.announce_results = false;
+        self.has_more = false;
+        self.set_highlighted_index(None, orders);
+        self.set_open(false, orders);
+        (*self.input_changed)("").map(|msg| orders.send_msg(msg));
+        self.emit(Event::InputChanged(String::new()), orders);
+        self.emit(Event::Cleared, orders);
+    }
+
+    /// Add `key` to the type-ahead buffer and highlight the next suggestion starting with it
+    fn type_ahead(&mut self, key: &str, orders: &mut impl Orders<Ms>) {
+        self.type_ahead_buffer.push_str(&key.to_lowercase());
+        let msg_mapper = self.msg_mapper;
+        self.type_ahead_timeout = Some(
+            orders.perform_cmd_with_handle(cmds::timeout(TYPE_AHEAD_TIMEOUT, move || {
+                msg_mapper(Msg::TypeAheadTimeout)
+            })),
+        );
+
+        let buffer = &self.type_ahead_buffer;
+        // repeating a character cycles through the suggestions starting with it
+        let is_repeat = buffer.chars().skip(1).all(|c| buffer.starts_with(c));
+        let start = match self.highlighted_index {
+            Some(idx) if is_repeat => idx + 1,
+            Some(idx) => idx,
+            None => 0,
+        };
+        let search = if is_repeat {
+            &buffer[..buffer.chars().next().map_or(0, char::len_utf8)]
+        } else {
+            buffer
+        };
+        let len = self.suggestions.len();
+        let found = (0..len).map(|offset| (start + offset) % len).find(|&idx| {
+            !self.is_disabled(idx)
+                && self.suggestions[idx]
+                    .display_text()
+                    .to_lowercase()
+                    .starts_with(search)
+        });
+        if found.is_some() {
+            self.set_highlighted_index(found, orders);
+        }
+    }
+
+    /// Open the menu with all the default suggestions, forgetting the query, as a select does, and highlight the selection
+    fn open_all(&mut self, orders: &mut impl Orders<Ms>) {
+        self.query.clear();
+        self.show_default_suggestions(orders);
+        self.highlight_selection(orders);
+        self.set_open(true, orders);
+    }
+
+    /// Highlight the current selection, if it is among the suggestions
+    fn highlight_selection(&mut self, orders: &mut impl Orders<Ms>) {
+        if let Some(selected) = self.selected_index() {
+            self.set_highlighted_index(Some(selected), orders);
+        }
+    }
+
+    /// Show the cached suggestions for the query, and ask for them if they aren't cached or should be revalidated
+    fn fetch_suggestions(&mut self, orders: &mut impl Orders<Ms>) {
+        if self.query.is_empty() {
+            return;
+        }
+        let query = self.query.clone();
+        let now = js_sys::Date::now();
+        let needs_fetch = match self.cache.as_mut().and_then(|cache| cache.get(&query, now)) {
+            Some(CacheHit {
+                suggestions,
+                needs_revalidation,
+            }) => {
+                self.set_suggestions(suggestions);
+                self.set_highlighted_index(None, orders);
+                needs_revalidation
+            }
+            None => true,
+        };
+        if needs_fetch {
+            self.emit(Event::Fetch(query), orders);
+        }
+    }
+
+    /// Replace the suggestions with the history and default suggestions if the input is empty and there are any
+    fn show_default_suggestions(&mut self, orders: &mut impl Orders<Ms>) {
+        if !self.query.is_empty() {
+            return;
+        }
+        let history = self
+            .history
+            .as_ref()
+            .filter(|history| !history.is_empty())
+            .map(|history| history.group(self.messages.recent.clone()));
+        let default_suggestions = self
+            .default_suggestions
+            .as_ref()
+            .map(|default_suggestions| default_suggestions());
+        match (history, default_suggestions) {
+            (None, None) => return,
+            (None, Some(default_suggestions)) => self.set_suggestions(default_suggestions),
+            (Some(history), default_suggestions) => {
+                self.set_suggestion_groups(vec![history]);
+                self.suggestions
+                    .extend(default_suggestions.into_iter().flatten());
+            }
+        }
+        self.set_highlighted_index(None, orders);
+    }
+
+    /// Show the children of the suggestion at `idx` in place of the suggestions, returns false if it doesn't have children
+    fn drill_down(&mut self, idx: usize, orders: &mut impl Orders<Ms>) -> bool {
+        let children = match (&self.children, self.suggestions.get(idx)) {
+            (Some(children), Some(suggestion)) => match children(suggestion) {
+                Some(children) => children,
+                None => return false,
+            },
+            _ => return false,
+        };
+        let parent = self.suggestions[idx].clone();
+        self.path.push(Level {
+            parent,
+            index: idx,
+            suggestions: std::mem::replace(&mut self.suggestions, children),
+            groups: std::mem::take(&mut self.groups),
+            match_ranges: std::mem::take(&mut self.match_ranges),
+        });
+        self.highlighted_index = None;
+        let first = (0..self.suggestions.len()).find(|&idx| !self.is_disabled(idx));
+        self.set_highlighted_index(first, orders);
+        true
+    }
+
+    /// Go back to the suggestions at `depth` of the path, highlighting the suggestion which was drilled into
+    fn go_up(&mut self, depth: usize, orders: &mut impl Orders<Ms>) {
+        self.path.truncate(depth + 1);
+        if depth + 1 != self.path.len() {
+            return;
+        }
+        if let Some(Level {
+            index,
+            suggestions,
+            groups,
+            match_ranges,
+            ..
+        }) = self.path.pop()
+        {
+            self.suggestions = suggestions;
+            self.groups = groups;
+            self.match_ranges = match_ranges;
+            self.highlighted_index = None;
+            self.set_highlighted_index(Some(index), orders);
+        }
+    }
+
+    /// Select the suggestion at `idx`, closing the menu and notifying the parent of the selection, and of a submission unless only part of the text was completed
+    fn select(&mut self, idx: usize, orders: &mut impl Orders<Ms>) {
+        let item = match self.suggestions.get(idx) {
+            Some(item) => item.clone(),
+            None => {
+                self.set_highlighted_index(None, orders);
+                return self.report(
+                    Error::SuggestionOutOfRange {
+                        index: idx,
+                        len: self.suggestions.len(),
+                    },
+                    orders,
+                );
+            }
+        };
+        self.complete_token(&item, orders);
+        self.set_open(false, orders);
+        self.set_highlighted_index(None, orders);
+        self.selected = Some(item.clone());
+        self.announce_selection = true;
+        self.record_history(item.clone());
+        (*self.suggestion_selected)(&item).map(|msg| orders.send_msg(msg));
+        self.emit(Event::Selected(item), orders);
+        // completing part of the text, eg. a mention, is just more editing
+        if self.query_mode == QueryMode::Whole {
+            self.submit(orders);
+        }
+    }
+
+    /// When completing part of the text, replace that part with the display text of `item`
+    fn complete_token(&mut self, item: &Suggestion, orders: &mut impl Orders<Ms>) {
+        if self.query_mode == QueryMode::Whole {
+            return;
+        }
+        if let Some(token) = self.token.take() {
+            let input = match self.input_ref.get() {
+                Some(input) => input,
+                None => return self.report(Error::MissingInput, orders),
+            };
+            if let Some(mut value) =
+                text_field::value(&input).filter(|value| value.get(token.span.clone()).is_some())
+            {
+                // a space, or for the last delimited value a delimiter, after the completion so that the user can carry on typing
+                let completion = match self.query_mode {
+                    QueryMode::Delimited(delimiter) if token.span.end == value.len() => {
+                        format!("{}{} ", item.display_text(), delimiter)
+                    }
+                    QueryMode::Delimited(_) => item.display_text(),
+                    QueryMode::Whole | QueryMode::Trigger(_) => format!("{} ", item.display_text()),
+                };
+                value.replace_range(token.span.clone(), &completion);
+                text_field::set_value(&input, &value);
+                text_field::set_caret(&input, &value, token.span.start + completion.len());
+            }
+        }
+        self.query.clear();
+    }
+
+    fn is_multiline(&self) -> bool {
+        matches!(self.input_ref.get(), Some(input) if text_field::is_multiline(&input))
+    }
+
+    /// Find the direction of the input and, for right to left text, place the menu below the input's right edge.
+    /// After a trigger the menu is instead placed at the caret as the text changes.
+    fn place_menu(&mut self) {
+        let input = match self.input_ref.get() {
+            Some(input) => input,
+            None => return,
+        };
+        if let Some(is_rtl) = text_field::is_rtl(&input) {
+            self.input_direction = if is_rtl {
+                Direction::Rtl
+            } else {
+                Direction::Ltr
+            };
+        }
+        if self.query_mode.is_triggered() {
+            return;
+        }
+        // left to right, the menu is where it falls below the input
+        self.menu_position = if self.direction() == Direction::Rtl {
+            let rect = input.get_bounding_client_rect();
+            self.menu_position_at(&input, rect.bottom(), rect.right())
+        } else {
+            None
+        };
+    }
+
+    /// Position the menu to start at the point `(top, left)` of the viewport, for right to left text its right edge is at `left`
+    fn menu_position_at(&self, input: &HtmlElement, top: f64, left: f64) -> Option<MenuPosition> {
+        let inline_start = match self.direction() {
+            Direction::Ltr => left,
+            Direction::Rtl => text_field::viewport_width(input)? - left,
+        };
+        Some(MenuPosition { top, inline_start })
+    }
+
+    fn submit(&self, orders: &mut impl Orders<Ms>) {
+        (*self.submit)().map(|msg| orders.send_msg(msg));
+        self.emit(Event::Submitted, orders);
+    }
+
+    fn set_open(&mut self, is_open: bool, orders: &mut impl Orders<Ms>) {
+        if self.is_open != is_open {
+            self.is_open = is_open;
+            if is_open {
+                self.announce_selection = false;
+                self.place_menu();
+            }
+            (*self.open_changed)(is_open).map(|msg| orders.send_msg(msg));
+            self.emit(
+                if is_open {
+                    Event::Opened
+                } else {
+                    Event::Closed
+                },
+                orders,
+            );
+        }
+    }
+
+    fn set_highlighted_index(
+        &mut self,
+        highlighted_index: Option<usize>,
+        orders: &mut impl Orders<Ms>,
+    ) {
+        if self.highlighted_index != highlighted_index {
+            self.highlighted_index = highlighted_index;
+            let highlighted = highlighted_index.and_then(|idx| self.suggestions.get(idx));
+            (*self.highlight_changed)(highlighted).map(|msg| orders.send_msg(msg));
+            self.emit(Event::HighlightChanged(highlighted_index), orders);
+            if highlighted_index.map(|idx| idx + 1) == Some(self.suggestions.len()) {
+                self.load_more(orders);
+            }
+        }
+    }
+
+    /// Ask for more suggestions, if there are more and they haven't already been asked for
+    fn load_more(&mut self, orders: &mut impl Orders<Ms>) {
+        if self.has_more && !self.loading_more {
+            self.loading_more = true;
+            self.emit(Event::LoadMore, orders);
+        }
+    }
+
+    fn report(&self, error: Error, orders: &mut impl Orders<Ms>) {
+        (*self.error)(&error).map(|msg| orders.send_msg(msg));
+        self.emit(Event::Error(error), orders);
+    }
+
+    fn emit(&self, event: Event<Suggestion>, orders: &mut impl Orders<Ms>) {
+        if let Some(event_mapper) = self.event_mapper {
+            orders.send_msg(event_mapper(event));
         }
     }
 
@@ -216,70 +1165,549 @@ impl<Ms: 'static, Suggestion: Clone> Model<Ms, Suggestion> {
     }
 }
 
-fn view<Ms: 'static, Suggestion>(
+impl<Ms, Suggestion> Model<Ms, Suggestion> {
+    fn direction(&self) -> Direction {
+        self.direction.unwrap_or(self.input_direction)
+    }
+}
+
+impl<Ms, Suggestion: SuggestionItem> Model<Ms, Suggestion> {
+    fn is_disabled(&self, idx: usize) -> bool {
+        matches!(self.suggestions.get(idx), Some(suggestion) if suggestion.is_disabled())
+    }
+
+    fn has_children(&self, suggestion: &Suggestion) -> bool {
+        match (&self.expandable, &self.children) {
+            (Some(expandable), _) => expandable(suggestion),
+            (None, Some(children)) => children(suggestion).is_some(),
+            (None, None) => false,
+        }
+    }
+
+    fn is_selected(&self, suggestion: &Suggestion) -> bool {
+        matches!(&self.selected, Some(selected) if selected.key() == suggestion.key())
+    }
+
+    /// The index of the current selection among the suggestions
+    fn selected_index(&self) -> Option<usize> {
+        (0..self.suggestions.len())
+            .find(|&idx| !self.is_disabled(idx) && self.is_selected(&self.suggestions[idx]))
+    }
+
+    fn is_selection_highlighted(&self) -> bool {
+        let highlighted = self
+            .highlighted_index
+            .and_then(|idx| self.suggestions.get(idx));
+        matches!(highlighted, Some(highlighted) if self.is_selected(highlighted))
+    }
+
+    /// After the suggestions are replaced, find the selection again if it was highlighted, otherwise highlight nothing.
+    /// This is done without `on_highlight` being called, as the suggestions are set outside of `update`.
+    fn reset_highlight(&mut self, keep_selection: bool) {
+        self.highlighted_index = if keep_selection {
+            self.selected_index()
+        } else {
+            None
+        };
+    }
+
+    fn suggestion_context(&self, idx: usize, suggestion: &Suggestion) -> SuggestionContext<'_> {
+        SuggestionContext {
+            index: idx,
+            is_highlighted: Some(idx) == self.highlighted_index,
+            is_selected: self.is_selected(suggestion),
+            is_disabled: self.is_disabled(idx),
+            has_children: self.has_children(suggestion),
+            direction: self.direction(),
+            query: &self.query,
+            match_ranges: self
+                .match_ranges
+                .get(idx)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+            group: self
+                .groups
+                .iter()
+                .find(|(range, _)| range.contains(&idx))
+                .map(|(range, label)| GroupContext {
+                    label,
+                    index: idx - range.start,
+                    len: range.len(),
+                }),
+        }
+    }
+}
+
+fn view<Ms: 'static, Suggestion: SuggestionItem>(
     model: &Model<Ms, Suggestion>,
-    suggestion_view: impl Fn(&Suggestion, bool) -> Node<Ms>,
-    input_attrs: Attrs,
-    menu_style: Style,
+    suggestion_view: impl Fn(&Suggestion, &SuggestionContext) -> Node<Ms>,
+    options: ViewOptions<Ms>,
 ) -> Vec<Node<Ms>> {
     let msg_mapper = model.msg_mapper;
+    let ViewOptions {
+        input_attrs,
+        menu_style,
+        clear_button,
+        class_names,
+        inline_styles,
+        textarea,
+        menu_closed,
+        wrapper_view,
+        input_view,
+        menu_view,
+    } = options;
 
-    nodes![div![
-        style! {
-            St::Display => "inline-block",
-            St::Position => "relative",
-        },
-        input![
-            el_ref(&model.input_ref),
-            input_attrs,
-            input_ev(Ev::Input, Msg::InputChange),
-            // input_ev(Ev::Change, Msg::Change),
-            simple_ev(Ev::Focus, Msg::InputFocus),
-            simple_ev(Ev::Blur, Msg::InputBlur),
-            keyboard_ev(Ev::KeyDown, Msg::InputKeyDown),
-            mouse_ev(Ev::Click, Msg::InputClick),
-        ]
-        .map_msg(msg_mapper),
-        if model.is_open {
-            div![
-                menu_style,
-                model
-                    .suggestions
+    let input = custom![
+        if textarea { Tag::TextArea } else { Tag::Input },
+        C![class_names.input],
+        el_ref(&model.input_ref),
+        input_attrs,
+        IF!(model.select_mode => select_input_attrs(model)),
+        input_ev(Ev::Input, Msg::InputChange),
+        // input_ev(Ev::Change, Msg::Change),
+        simple_ev(Ev::Focus, Msg::InputFocus),
+        simple_ev(Ev::Blur, Msg::InputBlur),
+        keyboard_ev(Ev::KeyDown, Msg::InputKeyDown),
+        mouse_ev(Ev::Click, Msg::InputClick),
+    ]
+    .map_msg(msg_mapper);
+
+    let menu = if model.is_open && !menu_closed {
+        let suggestion_nodes = model
+            .suggestions
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, suggestion)| {
+                let group_header = model
+                    .groups
                     .iter()
-                    .enumerate()
-                    .map(|(idx, suggestion)| {
-                        let mut suggestion_node =
-                            suggestion_view(suggestion, Some(idx) == model.highlighted_index);
-                        suggestion_node
-                            .add_event_handler(
-                                simple_ev(Ev::MouseEnter, Msg::SuggestionHover(idx))
-                                    .map_msg(msg_mapper),
-                            )
-                            .add_event_handler(
-                                simple_ev(Ev::Click, Msg::SuggestionClick(idx)).map_msg(msg_mapper),
-                            );
-                        suggestion_node
-                    })
-                    .collect::<Vec<_>>(),
-                ev(Ev::TouchStart, |_| Msg::SetIgnoreSuggestionBlur(true)).map_msg(msg_mapper),
-                ev(Ev::MouseEnter, |_| Msg::SetIgnoreSuggestionBlur(true)).map_msg(msg_mapper),
-                ev(Ev::MouseLeave, |_| Msg::SetIgnoreSuggestionBlur(false)).map_msg(msg_mapper),
-            ]
+                    .find(|(range, _)| range.start == idx && !range.is_empty())
+                    .map(|(_, label)| group_header_view(label, &class_names, inline_styles));
+                let context = model.suggestion_context(idx, suggestion);
+                let mut suggestion_node = suggestion_view(suggestion, &context);
+                suggestion_node.add_class(class_names.item);
+                if context.is_highlighted {
+                    suggestion_node.add_class(class_names.item_highlighted);
+                }
+                if context.is_selected {
+                    suggestion_node.add_class(class_names.item_selected);
+                }
+                if context.is_disabled {
+                    suggestion_node.add_class(class_names.item_disabled);
+                }
+                if context.has_children {
+                    suggestion_node
+                        .add_class(class_names.item_expandable)
+                        .add_attr("aria-expanded", "false")
+                        .add_child(expand_marker_view(
+                            idx,
+                            context.direction,
+                            &class_names,
+                            inline_styles,
+                            msg_mapper,
+                        ));
+                }
+                suggestion_node
+                    .add_attr("role", "option")
+                    .add_attr("aria-label", suggestion.display_text())
+                    .add_attr("aria-selected", context.is_selected.to_string());
+                suggestion_node
+                    .add_event_handler(
+                        simple_ev(Ev::MouseEnter, Msg::SuggestionHover(idx)).map_msg(msg_mapper),
+                    )
+                    .add_event_handler(
+                        simple_ev(Ev::Click, Msg::SuggestionClick(idx)).map_msg(msg_mapper),
+                    );
+                group_header.into_iter().chain(Some(suggestion_node))
+            })
+            .chain(if model.loading_more {
+                Some(status_view(
+                    &model.messages.loading_more,
+                    &class_names,
+                    inline_styles,
+                ))
+            } else {
+                None
+            })
+            .collect::<Vec<_>>();
+        let suggestion_nodes = if model.path.is_empty() {
+            suggestion_nodes
+        } else {
+            let breadcrumb =
+                breadcrumb_view(model, &class_names, inline_styles).map_msg(msg_mapper);
+            std::iter::once(breadcrumb)
+                .chain(suggestion_nodes)
+                .collect()
+        };
+        let mut menu = match menu_view {
+            Some(menu_view) => menu_view(suggestion_nodes),
+            None => div![suggestion_nodes],
+        };
+        menu.add_class(class_names.menu).add_attr("role", "listbox");
+        if inline_styles {
+            merge_style(&mut menu, default_menu_style());
+        }
+        if let Some(MenuPosition { top, inline_start }) = model.menu_position {
+            merge_style(
+                &mut menu,
+                style! {
+                    St::Top => px(top),
+                    model.direction().inline_start() => px(inline_start),
+                },
+            );
+        }
+        merge_style(&mut menu, menu_style);
+        if model.has_more {
+            menu.add_event_handler(
+                ev(Ev::Scroll, |event| {
+                    if scrolled_near_end(&event) {
+                        Some(Msg::MenuScrolledToEnd)
+                    } else {
+                        None
+                    }
+                })
+                .map_msg(msg_mapper),
+            );
+        }
+        menu.add_event_handler(
+            ev(Ev::TouchStart, |_| Msg::SetIgnoreSuggestionBlur(true)).map_msg(msg_mapper),
+        )
+        .add_event_handler(
+            ev(Ev::MouseEnter, |_| Msg::SetIgnoreSuggestionBlur(true)).map_msg(msg_mapper),
+        )
+        .add_event_handler(
+            ev(Ev::MouseLeave, |_| Msg::SetIgnoreSuggestionBlur(false)).map_msg(msg_mapper),
+        );
+        menu
+    } else {
+        empty![]
+    };
+
+    let children = nodes![
+        match input_view {
+            Some(input_view) => input_view(input),
+            None => input,
+        },
+        if model.select_mode {
+            toggle_button_view(model, &class_names, inline_styles).map_msg(msg_mapper)
+        } else {
+            empty![]
+        },
+        if clear_button {
+            let mut clear_button = clear_button_view(
+                &model.messages.clear,
+                model.direction(),
+                &class_names,
+                inline_styles,
+            );
+            if model.select_mode && inline_styles {
+                // make room for the toggle button
+                merge_style(
+                    &mut clear_button,
+                    style! { model.direction().inline_end() => "1.5em" },
+                );
+            }
+            clear_button.map_msg(msg_mapper)
         } else {
             empty![]
         },
-    ]]
+        menu,
+        live_region_view(model, menu_closed, &class_names, inline_styles),
+    ];
+    let mut wrapper = match wrapper_view {
+        Some(wrapper_view) => wrapper_view(children),
+        None => div![children],
+    };
+    wrapper.add_class(class_names.wrapper);
+    if let Some(direction) = model.direction {
+        wrapper.add_attr("dir", direction.as_str());
+    }
+    if inline_styles {
+        merge_style(
+            &mut wrapper,
+            style! {
+                St::Display => "inline-block",
+                St::Position => "relative",
+            },
+        );
+    }
+    nodes![wrapper]
+}
+
+/// Add styles to a node, if it is an element
+fn merge_style<Ms>(node: &mut Node<Ms>, style: Style) {
+    if let Node::Element(el) = node {
+        el.style.merge(style);
+    }
+}
+
+fn default_menu_style() -> Style {
+    style! {
+      St::BorderRadius => "3px",
+      St::BoxShadow => "0 2px 12px rgba(0, 0, 0, 0.1)",
+      St::Background => "rgba(255, 255, 255, 0.9)",
+      St::Padding => "2px 0",
+      St::FontSize => "90%",
+      St::Position => "fixed",
+      St::Overflow => "auto",
+      // limited so that the menu scrolls, which loads more suggestions, see `Model::set_has_more`
+      St::MaxHeight => "50vh",
+    }
+}
+
+fn clear_button_view(
+    label: &str,
+    direction: Direction,
+    class_names: &ClassNames,
+    inline_styles: bool,
+) -> Node<Msg> {
+    button![
+        C![class_names.clear_button],
+        attrs! {
+            At::Type => "button",
+            At::AriaLabel => label,
+        },
+        IF!(inline_styles => style! {
+            St::Position => "absolute",
+            direction.inline_end() => "0",
+            St::Top => "0",
+            St::Bottom => "0",
+            St::Border => "none",
+            St::Background => "none",
+            St::Cursor => "pointer",
+        }),
+        "×",
+        // keep the focus in the input
+        ev(Ev::MouseDown, |ev| ev.prevent_default()),
+        simple_ev(Ev::Click, Msg::ClearClick),
+    ]
+}
+
+/// Attributes of the input in select mode, where it shows the selection while closed and the query while open
+fn select_input_attrs<Ms, Suggestion: SuggestionItem>(model: &Model<Ms, Suggestion>) -> Attrs {
+    let label = model
+        .selected
+        .as_ref()
+        .map(SuggestionItem::display_text)
+        .unwrap_or_default();
+    let is_editable = model.is_open && !model.type_ahead;
+    let mut attrs = attrs! {
+        At::from("role") => "combobox",
+        At::AriaExpanded => model.is_open,
+        At::AriaHasPopup => "listbox",
+        At::AutoComplete => "off",
+        At::ReadOnly => (!is_editable).as_at_value(),
+    };
+    if is_editable {
+        attrs.add(At::Value, &model.query);
+        attrs.add(At::Placeholder, label);
+    } else {
+        attrs.add(At::Value, label);
+    }
+    attrs
+}
+
+fn toggle_button_view<Ms, Suggestion>(
+    model: &Model<Ms, Suggestion>,
+    class_names: &ClassNames,
+    inline_styles: bool,
+) -> Node<Msg> {
+    button![
+        C![class_names.toggle_button],
+        attrs! {
+            At::Type => "button",
+            At::AriaLabel => model.messages.show_suggestions,
+            At::AriaHasPopup => "listbox",
+            At::AriaExpanded => model.is_open,
+            At::TabIndex => -1,
+        },
+        IF!(inline_styles => style! {
+            St::Position => "absolute",
+            model.direction().inline_end() => "0",
+            St::Top => "0",
+            St::Bottom => "0",
+            St::Border => "none",
+            St::Background => "none",
+            St::Cursor => "pointer",
+        }),
+        "▾",
+        // keep the focus in the input
+        ev(Ev::MouseDown, |ev| ev.prevent_default()),
+        simple_ev(Ev::Click, Msg::ToggleClick),
+    ]
+}
+
+fn group_header_view<Ms>(label: &str, class_names: &ClassNames, inline_styles: bool) -> Node<Ms> {
+    div![
+        C![class_names.group_header],
+        IF!(inline_styles => style! {
+            St::FontWeight => "bold",
+            St::Cursor => "default",
+        }),
+        label,
+    ]
+}
+
+/// The parents of the suggestions shown, each of which can be clicked to go back to its level
+fn breadcrumb_view<Ms, Suggestion: SuggestionItem>(
+    model: &Model<Ms, Suggestion>,
+    class_names: &ClassNames,
+    inline_styles: bool,
+) -> Node<Msg> {
+    div![
+        C![class_names.breadcrumb],
+        IF!(inline_styles => style! {
+            St::Color => "gray",
+            St::Cursor => "pointer",
+        }),
+        span![
+            attrs! {
+                At::AriaLabel => model.messages.back,
+            },
+            "‹",
+            ev(Ev::Click, move |_| Msg::BreadcrumbClick(0)),
+        ],
+        model.path.iter().enumerate().map(|(depth, level)| {
+            span![
+                if depth == 0 { " " } else { " › " },
+                level.parent.display_text(),
+                ev(Ev::Click, move |_| Msg::BreadcrumbClick(depth + 1)),
+            ]
+        }),
+    ]
+}
+
+/// A visually hidden region which screen readers announce the text of whenever it changes: the number of suggestions while the menu is open, and the selection after one is made
+fn live_region_view<Ms, Suggestion: SuggestionItem>(
+    model: &Model<Ms, Suggestion>,
+    menu_closed: bool,
+    class_names: &ClassNames,
+    inline_styles: bool,
+) -> Node<Ms> {
+    let announcement = if model.is_open && !menu_closed {
+        if model.announce_results {
+            (model.messages.results_available)(model.suggestions.len())
+        } else {
+            String::new()
+        }
+    } else {
+        match &model.selected {
+            Some(selected) if model.announce_selection => {
+                (model.messages.selected)(&selected.display_text())
+            }
+            _ => String::new(),
+        }
+    };
+    div![
+        C![class_names.live_region],
+        attrs! {
+            At::AriaLive => "polite",
+            At::AriaAtomic => "true",
+        },
+        IF!(inline_styles => style! {
+            St::Position => "absolute",
+            St::Width => "1px",
+            St::Height => "1px",
+            St::Margin => "-1px",
+            St::Padding => "0",
+            St::Overflow => "hidden",
+            St::Clip => "rect(0, 0, 0, 0)",
+            St::WhiteSpace => "nowrap",
+            St::Border => "0",
+        }),
+        announcement,
+    ]
 }
 
-pub fn default_suggestion_view<Suggestion: ToString, Ms>(
+fn status_view<Ms>(text: &str, class_names: &ClassNames, inline_styles: bool) -> Node<Ms> {
+    div![
+        C![class_names.status],
+        IF!(inline_styles => style! {
+            St::Color => "gray",
+            St::FontStyle => "italic",
+            St::Cursor => "default",
+        }),
+        text,
+    ]
+}
+
+/// The › at the end of a suggestion with children, which shows the children when clicked rather than selecting the suggestion
+fn expand_marker_view<Ms: 'static>(
+    idx: usize,
+    direction: Direction,
+    class_names: &ClassNames,
+    inline_styles: bool,
+    msg_mapper: fn(Msg) -> Ms,
+) -> Node<Ms> {
+    span![
+        C![class_names.marker],
+        IF!(inline_styles => style! {
+            St::Float => direction.inline_end(),
+        }),
+        attrs! {
+            At::AriaHidden => "true",
+        },
+        "›",
+        ev(Ev::Click, move |event| {
+            event.stop_propagation();
+            Msg::ExpandClick(idx)
+        })
+        .map_msg(msg_mapper),
+    ]
+}
+
+/// Whether the element the event happened on is scrolled to within a few rows of its end
+fn scrolled_near_end(event: &web_sys::Event) -> bool {
+    match event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+    {
+        Some(element) => {
+            element.scroll_top() + element.client_height() + SCROLL_END_THRESHOLD
+                >= element.scroll_height()
+        }
+        None => false,
+    }
+}
+
+/// How long, in milliseconds, after the last character typed the type-ahead buffer is emptied
+const TYPE_AHEAD_TIMEOUT: u32 = 1000;
+
+/// How close, in pixels, the menu must be scrolled to its end for more suggestions to be loaded
+const SCROLL_END_THRESHOLD: i32 = 40;
+
+pub fn default_suggestion_view<Suggestion: SuggestionItem, Ms>(
     suggestion: &Suggestion,
-    is_highlighted: bool,
+    context: &SuggestionContext,
 ) -> Node<Ms> {
     div![
         style! {
-            St::Background => if is_highlighted { "lightgray" } else { "white" },
+            St::Background => if context.is_highlighted { "lightgray" } else { "white" },
+            St::Color => if context.is_disabled { "gray" } else { "inherit" },
             St::Cursor => "default",
         },
-        suggestion.to_string(),
+        highlight_matches(&suggestion.display_text(), context.match_ranges),
+        IF!(context.is_selected => span![
+            style! {
+                St::Float => context.direction.inline_end(),
+            },
+            "✓",
+        ]),
     ]
 }
+
+/// Split the text into nodes, with the parts in `match_ranges` in bold, ranges which aren't on character boundaries of the text are ignored
+pub fn highlight_matches<Ms>(text: &str, match_ranges: &[Range<usize>]) -> Vec<Node<Ms>> {
+    let mut nodes = Vec::with_capacity(match_ranges.len() * 2 + 1);
+    let mut end = 0;
+    for range in match_ranges {
+        if range.start < end || text.get(range.clone()).is_none() {
+            continue;
+        }
+        nodes.push(Node::new_text(text[end..range.start].to_owned()));
+        nodes.push(strong![&text[range.clone()]]);
+        end = range.end;
+    }
+    nodes.push(Node::new_text(text[end..].to_owned()));
+    nodes
+}
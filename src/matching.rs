@@ -0,0 +1,315 @@
+//! Matching queries against the text of suggestions, ignoring case, diacritics and Unicode compatibility differences,
+//! so that eg. "cote" matches "Côte d'Ivoire" and "strasse" matches "Straße". Matches are byte ranges of the original text, for highlighting, see `highlight_matches`.
+use std::ops::Range;
+use unicode_normalization::char::{decompose_compatible, is_combining_mark};
+
+/// Text normalized for matching, which remembers where each part of it came from in the original text
+#[derive(Debug, Clone)]
+pub struct NormalizedText {
+    text: String,
+    /// For each char of `text`, its byte offset in `text` and the byte range of the original char it came from
+    origins: Vec<(usize, Range<usize>)>,
+    original_len: usize,
+}
+
+impl NormalizedText {
+    /// Decompose `original` into its compatibility form (NFKD), drop the combining marks which that splits off, such as accents, and case fold it
+    pub fn new(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+        for (idx, c) in original.char_indices() {
+            let origin = idx..idx + c.len_utf8();
+            decompose_compatible(c, |decomposed| {
+                if is_combining_mark(decomposed) {
+                    return;
+                }
+                // lower casing can also produce combining marks, eg. for 'İ'
+                for folded in decomposed
+                    .to_lowercase()
+                    .filter(|&lower| !is_combining_mark(lower))
+                    .flat_map(fold)
+                {
+                    origins.push((text.len(), origin.clone()));
+                    text.push(folded);
+                }
+            });
+        }
+        Self {
+            text,
+            origins,
+            original_len: original.len(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The byte range of the original text which the byte range `range` of the normalized text came from
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        let first = self
+            .origins
+            .partition_point(|(offset, _)| *offset < range.start);
+        let start = self
+            .origins
+            .get(first)
+            .map_or(self.original_len, |(_, origin)| origin.start);
+        let last = self
+            .origins
+            .partition_point(|(offset, _)| *offset < range.end);
+        let end = match last.checked_sub(1).and_then(|last| self.origins.get(last)) {
+            Some((_, origin)) if last > first => origin.end,
+            _ => start,
+        };
+        start..end
+    }
+}
+
+/// Case fold a lower case char. Lower casing already folds all but a few chars, and most of those are decomposed by NFKD, eg. 'ﬀ', leaving these.
+fn fold(lower: char) -> impl Iterator<Item = char> {
+    let (first, second) = match lower {
+        'ß' => ('s', Some('s')),
+        'ς' => ('σ', None),
+        lower => (lower, None),
+    };
+    std::iter::once(first).chain(second)
+}
+
+/// The text as it is compared when matching
+pub fn normalize(text: &str) -> String {
+    NormalizedText::new(text).text
+}
+
+/// If `candidate` starts with `query`, the range of `candidate` which matched
+pub fn prefix_match(candidate: &str, query: &str) -> Option<Range<usize>> {
+    let candidate = NormalizedText::new(candidate);
+    let query = normalize(query);
+    if candidate.as_str().starts_with(&query) {
+        Some(candidate.original_range(0..query.len()))
+    } else {
+        None
+    }
+}
+
+/// The range of `candidate` where `query` first appears in it
+pub fn substring_match(candidate: &str, query: &str) -> Option<Range<usize>> {
+    let candidate = NormalizedText::new(candidate);
+    let query = normalize(query);
+    let start = candidate.as_str().find(&query)?;
+    Some(candidate.original_range(start..start + query.len()))
+}
+
+/// How well a candidate matched a query, see `token_match`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMatch {
+    /// The ranges of the candidate which matched, in order
+    pub ranges: Vec<Range<usize>>,
+    /// Higher is better, for ranking candidates
+    pub score: u32,
+}
+
+/// Score for each word of the query matched
+const WORD_SCORE: u32 = 10;
+/// Extra score for a word of the query matching a whole word of the candidate rather than the start of one
+const WHOLE_WORD_SCORE: u32 = 5;
+/// Extra score for the query matching from the first word of the candidate
+const FIRST_WORD_SCORE: u32 = 10;
+/// Extra score for the words of the query matching consecutive words of the candidate, in the same order
+const IN_ORDER_SCORE: u32 = 20;
+
+/// Match each word of `query` to the start of a different word of `candidate`, in any order, so that eg. "kingdom united" matches "United Kingdom".
+/// Words are separated by anything that isn't alphanumeric, and compared normalized, see `NormalizedText`.
+/// Matches of the words in order and next to each other score higher, so that "united kin" ranks "United Kingdom" above "Kingdom of the United Netherlands".
+pub fn token_match(candidate: &str, query: &str) -> Option<TokenMatch> {
+    normalized_token_match(&NormalizedText::new(candidate), &normalize(query))
+}
+
+/// As `token_match`, for a candidate which is already normalized and a query returned by `normalize`, eg. to normalize the candidates once when searching them repeatedly
+pub fn normalized_token_match(candidate: &NormalizedText, query: &str) -> Option<TokenMatch> {
+    let candidate_words = words(candidate.as_str());
+    let query_words: Vec<&str> = words(query).into_iter().map(|word| &query[word]).collect();
+    if query_words.is_empty() {
+        return Some(TokenMatch {
+            ranges: Vec::new(),
+            score: 0,
+        });
+    }
+
+    let assigned = assign_words(candidate.as_str(), &candidate_words, &query_words, false)
+        .or_else(|| assign_words(candidate.as_str(), &candidate_words, &query_words, true))?;
+
+    let mut score = 0;
+    for (query_word, &word_idx) in query_words.iter().zip(&assigned) {
+        score += WORD_SCORE;
+        if candidate_words[word_idx].len() == query_word.len() {
+            score += WHOLE_WORD_SCORE;
+        }
+    }
+    if assigned.first() == Some(&0) {
+        score += FIRST_WORD_SCORE;
+    }
+    if assigned.windows(2).all(|pair| pair[1] == pair[0] + 1) {
+        score += IN_ORDER_SCORE;
+    }
+
+    let mut ranges: Vec<_> = query_words
+        .iter()
+        .zip(&assigned)
+        .map(|(query_word, &word_idx)| {
+            let start = candidate_words[word_idx].start;
+            candidate.original_range(start..start + query_word.len())
+        })
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    Some(TokenMatch { ranges, score })
+}
+
+/// Find a different word of the candidate starting with each query word, returning the index of the candidate word for each query word.
+/// Each query word takes the word after the previous one's if it can, so that words in order match in order,
+/// otherwise the first it can. If `longest_first`, the longest query words, which have fewest words they can match, choose first.
+fn assign_words(
+    candidate: &str,
+    candidate_words: &[Range<usize>],
+    query_words: &[&str],
+    longest_first: bool,
+) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..query_words.len()).collect();
+    if longest_first {
+        order.sort_by_key(|&idx| std::cmp::Reverse(query_words[idx].len()));
+    }
+    let mut assigned = vec![0; query_words.len()];
+    let mut used = vec![false; candidate_words.len()];
+    let mut previous: Option<usize> = None;
+    for idx in order {
+        let fits = |word_idx: usize| {
+            !used[word_idx]
+                && candidate[candidate_words[word_idx].clone()].starts_with(query_words[idx])
+        };
+        let next = previous
+            .map(|previous| previous + 1)
+            .filter(|&next| next < candidate_words.len() && fits(next));
+        let word_idx =
+            next.or_else(|| (0..candidate_words.len()).find(|&word_idx| fits(word_idx)))?;
+        used[word_idx] = true;
+        assigned[idx] = word_idx;
+        previous = Some(word_idx);
+    }
+    Some(assigned)
+}
+
+/// The byte ranges of the words of `text`
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(word_start)) => {
+                words.push(word_start..idx);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        words.push(word_start..text.len());
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diacritics_are_ignored() {
+        assert_eq!(prefix_match("Côte d'Ivoire", "cote"), Some(0..5));
+        assert_eq!(prefix_match("Côte d'Ivoire", "CÔTE"), Some(0..5));
+        assert_eq!(substring_match("Ivoire, Côte d'", "cote"), Some(8..13));
+        assert_eq!(prefix_match("Côte d'Ivoire", "cota"), None);
+    }
+
+    #[test]
+    fn ligatures_match_their_letters() {
+        assert_eq!(normalize("ﬁnance"), "finance");
+        assert_eq!(prefix_match("ﬁnance", "fin"), Some(0..4));
+        // part of a ligature matches the whole of it
+        assert_eq!(prefix_match("ﬁnance", "f"), Some(0..3));
+        assert_eq!(substring_match("deﬁne", "fi"), Some(2..5));
+    }
+
+    #[test]
+    fn dotted_capital_i_matches_i() {
+        assert_eq!(normalize("İstanbul"), "istanbul");
+        assert_eq!(prefix_match("İstanbul", "ist"), Some(0..4));
+        assert_eq!(substring_match("Kİlis", "il"), Some(1..4));
+    }
+
+    #[test]
+    fn sharp_s_matches_ss() {
+        assert_eq!(normalize("Straße"), "strasse");
+        assert_eq!(normalize("STRAẞE"), "strasse");
+        assert_eq!(prefix_match("Straße", "STRASSE"), Some(0..7));
+        // part of the ß matches the whole of it
+        assert_eq!(prefix_match("Straße", "stras"), Some(0..6));
+        assert_eq!(substring_match("Hauptstraße", "strasse"), Some(5..12));
+    }
+
+    #[test]
+    fn final_sigma_matches_sigma() {
+        assert_eq!(normalize("ΟΔΟΣ"), normalize("οδος"));
+        assert_eq!(prefix_match("οδος", "ΟΔΟΣ"), Some(0..8));
+    }
+
+    #[test]
+    fn empty_query_matches_an_empty_range_at_the_start() {
+        assert_eq!(prefix_match("Côte", ""), Some(0..0));
+        assert_eq!(substring_match("Côte", ""), Some(0..0));
+        assert_eq!(prefix_match("", ""), Some(0..0));
+        assert_eq!(prefix_match("", "a"), None);
+    }
+
+    #[test]
+    fn words_match_in_any_order() {
+        let matched = token_match("United Kingdom", "kingdom united").unwrap();
+        assert_eq!(matched.ranges, [0..6, 7..14]);
+        assert!(token_match("United Kingdom", "kingdom states").is_none());
+        // each query word needs a word of its own
+        assert!(token_match("United Kingdom", "united uni").is_none());
+    }
+
+    #[test]
+    fn words_in_order_rank_higher() {
+        let in_order = token_match("United Kingdom", "united kin").unwrap();
+        let apart = token_match("Kingdom of the United Netherlands", "united kin").unwrap();
+        assert!(in_order.score > apart.score);
+        assert_eq!(apart.ranges, [0..3, 15..21]);
+
+        let reversed = token_match("United Kingdom", "kingdom united").unwrap();
+        assert!(in_order.score > reversed.score);
+    }
+
+    #[test]
+    fn longer_words_choose_first_when_needed() {
+        // "s" would take "saint", leaving nothing for "saint", unless "saint" chooses first
+        let matched = token_match("Saint Sebastian", "s saint").unwrap();
+        assert_eq!(matched.ranges, [0..5, 6..7]);
+    }
+
+    #[test]
+    fn empty_query_matches_with_no_score() {
+        let matched = token_match("United Kingdom", " ").unwrap();
+        assert!(matched.ranges.is_empty());
+        assert_eq!(matched.score, 0);
+    }
+
+    #[test]
+    fn empty_ranges_stay_empty() {
+        let text = NormalizedText::new("Côte");
+        assert_eq!(text.as_str(), "cote");
+        assert_eq!(text.original_range(0..0), 0..0);
+        assert_eq!(text.original_range(2..2), 3..3);
+        assert_eq!(text.original_range(4..4), 5..5);
+        assert_eq!(text.original_range(1..3), 1..4);
+    }
+}
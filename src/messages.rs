@@ -0,0 +1,56 @@
+//! Text the component shows or announces, which can be translated
+use std::fmt;
+use std::rc::Rc;
+
+/// The text of the component, with English defaults. Functions are used where the text depends on something, eg. a count, so that it can be pluralized.
+/// They can be closures, eg. capturing the current locale or a table of translations.
+#[derive(Clone)]
+pub struct Messages {
+    /// The label of the group of previous selections, see `Model::with_history`
+    pub recent: String,
+    /// The accessible label of the clear button, see `ViewBuilder::with_clear_button`
+    pub clear: String,
+    /// The accessible label of the button which opens the menu, see `Model::with_select_mode`
+    pub show_suggestions: String,
+    /// The accessible label of the start of the breadcrumb, which goes back to the top level, see `Model::with_children`
+    pub back: String,
+    /// Shown at the end of the menu while more suggestions are loaded, see `Model::set_has_more`
+    pub loading_more: String,
+    /// Announced to screen readers when the menu shows this number of suggestions
+    pub results_available: Rc<dyn Fn(usize) -> String>,
+    /// Announced to screen readers when the suggestion with this display text is selected
+    pub selected: Rc<dyn Fn(&str) -> String>,
+}
+
+impl fmt::Debug for Messages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Messages")
+            .field("recent", &self.recent)
+            .field("clear", &self.clear)
+            .field("show_suggestions", &self.show_suggestions)
+            .field("back", &self.back)
+            .field("loading_more", &self.loading_more)
+            .finish()
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self {
+            recent: "Recent".to_owned(),
+            clear: "Clear".to_owned(),
+            show_suggestions: "Show suggestions".to_owned(),
+            back: "Back to the top".to_owned(),
+            loading_more: "Loading more…".to_owned(),
+            results_available: Rc::new(|count| match count {
+                0 => "No results".to_owned(),
+                1 => "1 result available, use up and down arrows to move to it".to_owned(),
+                count => format!(
+                    "{} results available, use up and down arrows to move through them",
+                    count
+                ),
+            }),
+            selected: Rc::new(|display_text| format!("{} selected", display_text)),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Finding the part of the input's text which suggestions are looked up for
+use std::ops::Range;
+
+/// How the query is taken from the text of the input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum QueryMode {
+    /// The whole text is the query
+    Whole,
+    /// The query is the text between a trigger character at the start of a word and the caret, eg. `@` for mentions
+    Trigger(char),
+    /// The text holds several values separated by a delimiter, eg. `,`, and the query is the value at the caret
+    Delimited(char),
+}
+
+/// The part of the text being completed, as byte ranges of the text
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    /// The text which is replaced when a suggestion is selected
+    pub(crate) span: Range<usize>,
+    /// The text which suggestions are looked up for
+    pub(crate) query: Range<usize>,
+}
+
+impl QueryMode {
+    /// Whether the query only exists after a trigger, in which case the menu is shown at the caret
+    pub(crate) fn is_triggered(self) -> bool {
+        matches!(self, QueryMode::Trigger(_))
+    }
+
+    /// Split the text into the values separated by the delimiter, or just the whole text
+    pub(crate) fn values(self, text: &str) -> Vec<String> {
+        match self {
+            QueryMode::Delimited(delimiter) => text
+                .split(delimiter)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            QueryMode::Whole | QueryMode::Trigger(_) => vec![text.to_owned()],
+        }
+    }
+
+    /// Find the token being completed when the caret is at byte offset `caret` of `text`
+    pub(crate) fn token(self, text: &str, caret: usize) -> Option<Token> {
+        match self {
+            QueryMode::Whole => Some(Token {
+                span: 0..text.len(),
+                query: 0..text.len(),
+            }),
+            QueryMode::Trigger(trigger) => {
+                let before = text.get(..caret)?;
+                let start = before.rfind(|c: char| c == trigger || c.is_whitespace())?;
+                if !before[start..].starts_with(trigger) {
+                    return None;
+                }
+                // the trigger only counts at the start of a word, so that eg. email addresses don't trigger mentions
+                if before[..start].ends_with(|c: char| !c.is_whitespace()) {
+                    return None;
+                }
+                Some(Token {
+                    span: start..caret,
+                    query: start + trigger.len_utf8()..caret,
+                })
+            }
+            QueryMode::Delimited(delimiter) => {
+                let start = text
+                    .get(..caret)?
+                    .rfind(delimiter)
+                    .map_or(0, |idx| idx + delimiter.len_utf8());
+                let end = text[caret..]
+                    .find(delimiter)
+                    .map_or(text.len(), |idx| caret + idx);
+                // whitespace around the value isn't part of it
+                let value = text[start..end].trim_start();
+                let start = end - value.len();
+                let end = start + value.trim_end().len();
+                Some(Token {
+                    span: start..end,
+                    query: start..end,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The query and the span of the token at the caret, as text
+    fn token(mode: QueryMode, text: &str, caret: usize) -> Option<(&str, &str)> {
+        mode.token(text, caret)
+            .map(|token| (&text[token.query], &text[token.span]))
+    }
+
+    #[test]
+    fn whole_text_is_the_query() {
+        assert_eq!(token(QueryMode::Whole, "abc", 1), Some(("abc", "abc")));
+    }
+
+    #[test]
+    fn trigger_starts_a_word() {
+        let mode = QueryMode::Trigger('@');
+        assert_eq!(token(mode, "hi @bo", 6), Some(("bo", "@bo")));
+        assert_eq!(token(mode, "@bo", 3), Some(("bo", "@bo")));
+        // only the part before the caret is the query
+        assert_eq!(token(mode, "@bob", 2), Some(("b", "@b")));
+        assert_eq!(token(mode, "a@b", 3), None);
+        assert_eq!(token(mode, "@bo b", 5), None);
+        assert_eq!(token(mode, "no trigger", 10), None);
+    }
+
+    #[test]
+    fn bare_trigger_has_an_empty_query() {
+        let mode = QueryMode::Trigger('@');
+        assert_eq!(token(mode, "@", 1), Some(("", "@")));
+        assert_eq!(token(mode, "hi @", 4), Some(("", "@")));
+    }
+
+    #[test]
+    fn delimited_values_are_trimmed() {
+        let mode = QueryMode::Delimited(',');
+        assert_eq!(token(mode, "a,  bc , d", 5), Some(("bc", "bc")));
+        assert_eq!(token(mode, "a,  bc , d", 10), Some(("d", "d")));
+        assert_eq!(
+            mode.values(" a , bc,d "),
+            ["a".to_owned(), "bc".to_owned(), "d".to_owned()]
+        );
+    }
+
+    #[test]
+    fn empty_delimited_values() {
+        let mode = QueryMode::Delimited(',');
+        assert_eq!(mode.values("a,,b"), ["a".to_owned(), "b".to_owned()]);
+        assert_eq!(mode.values(" , "), Vec::<String>::new());
+        let token = mode.token("a,,b", 2).unwrap();
+        assert_eq!(token.span, 2..2);
+        assert_eq!(token.query, 2..2);
+    }
+
+    #[test]
+    fn caret_after_a_delimiter() {
+        let mode = QueryMode::Delimited(',');
+        // the caret is at the start of the next value
+        assert_eq!(token(mode, "a,b", 2), Some(("b", "b")));
+        assert_eq!(token(mode, "a, b", 2), Some(("b", "b")));
+        let token = mode.token("a,", 2).unwrap();
+        assert_eq!(token.span, 2..2);
+    }
+
+    #[test]
+    fn multi_byte_text() {
+        let trigger = QueryMode::Trigger('＠');
+        let text = "héllo ＠zoë";
+        assert_eq!(token(trigger, text, text.len()), Some(("zoë", "＠zoë")));
+        let delimited = QueryMode::Delimited('、');
+        let text = "東京、大阪";
+        assert_eq!(token(delimited, text, text.len()), Some(("大阪", "大阪")));
+        assert_eq!(token(delimited, text, 3), Some(("東京", "東京")));
+        // a caret inside a character has no token
+        assert_eq!(token(delimited, text, 1), None);
+        assert_eq!(token(trigger, "＠zoë", 1), None);
+    }
+}
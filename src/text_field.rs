@@ -0,0 +1,165 @@
+//! Access to the text and caret of the element the user types into, which may be an `input` or a `textarea`.
+//! Positions are byte offsets into the text, the DOM counts them in UTF-16 code units.
+use seed::prelude::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlTextAreaElement};
+
+pub(crate) fn value(element: &HtmlElement) -> Option<String> {
+    if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+        Some(input.value())
+    } else {
+        element
+            .dyn_ref::<HtmlTextAreaElement>()
+            .map(HtmlTextAreaElement::value)
+    }
+}
+
+pub(crate) fn set_value(element: &HtmlElement, value: &str) {
+    if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+        input.set_value(value);
+    } else if let Some(textarea) = element.dyn_ref::<HtmlTextAreaElement>() {
+        textarea.set_value(value);
+    }
+}
+
+/// Whether the element accepts more than one line of text, in which case enter doesn't submit
+pub(crate) fn is_multiline(element: &HtmlElement) -> bool {
+    element.dyn_ref::<HtmlTextAreaElement>().is_some()
+}
+
+/// The position of the caret in `text`, which should be the element's value
+pub(crate) fn caret(element: &HtmlElement, text: &str) -> Option<usize> {
+    let selection_start = if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+        input.selection_start()
+    } else {
+        element.dyn_ref::<HtmlTextAreaElement>()?.selection_start()
+    };
+    Some(from_utf16_offset(text, selection_start.ok()??))
+}
+
+/// Move the caret to `position` in `text`, which should be the element's value
+pub(crate) fn set_caret(element: &HtmlElement, text: &str, position: usize) {
+    let position = to_utf16_offset(text, position);
+    if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+        let _ = input.set_selection_range(position, position);
+    } else if let Some(textarea) = element.dyn_ref::<HtmlTextAreaElement>() {
+        let _ = textarea.set_selection_range(position, position);
+    }
+}
+
+fn from_utf16_offset(text: &str, utf16_offset: u32) -> usize {
+    let mut units = 0;
+    for (idx, c) in text.char_indices() {
+        if units >= utf16_offset as usize {
+            return idx;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn to_utf16_offset(text: &str, offset: usize) -> u32 {
+    text.char_indices()
+        .take_while(|(idx, _)| *idx < offset)
+        .map(|(_, c)| c.len_utf16() as u32)
+        .sum()
+}
+
+/// Whether the text of the element is laid out right to left
+pub(crate) fn is_rtl(element: &HtmlElement) -> Option<bool> {
+    let computed_style = element
+        .owner_document()?
+        .default_view()?
+        .get_computed_style(element)
+        .ok()??;
+    Some(computed_style.get_property_value("direction").ok()? == "rtl")
+}
+
+/// The width of the viewport the element is in, not including any scrollbar
+pub(crate) fn viewport_width(element: &HtmlElement) -> Option<f64> {
+    Some(f64::from(
+        element.owner_document()?.document_element()?.client_width(),
+    ))
+}
+
+/// Styles which affect the layout of text in the element, copied to the mirror element
+const MIRRORED_PROPERTIES: &[&str] = &[
+    "direction",
+    "box-sizing",
+    "width",
+    "height",
+    "overflow-x",
+    "overflow-y",
+    "border-top-width",
+    "border-right-width",
+    "border-bottom-width",
+    "border-left-width",
+    "border-style",
+    "padding-top",
+    "padding-right",
+    "padding-bottom",
+    "padding-left",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "font-stretch",
+    "font-size",
+    "line-height",
+    "font-family",
+    "text-align",
+    "text-transform",
+    "text-indent",
+    "letter-spacing",
+    "word-spacing",
+    "tab-size",
+];
+
+/// The point in the viewport just below the caret at `position`, as `(top, left)`.
+/// This is measured by laying out the text before the caret in a hidden element styled like the input.
+pub(crate) fn caret_coordinates(element: &HtmlElement, position: usize) -> Option<(f64, f64)> {
+    let text = value(element)?;
+    let before_caret = text.get(..position)?;
+    let document = element.owner_document()?;
+    let body = document.body()?;
+    let computed_style = document
+        .default_view()?
+        .get_computed_style(element)
+        .ok()??;
+
+    let mirror = document
+        .create_element("div")
+        .ok()?
+        .dyn_into::<HtmlElement>()
+        .ok()?;
+    let mirror_style = mirror.style();
+    for property in MIRRORED_PROPERTIES {
+        let value = computed_style.get_property_value(property).ok()?;
+        let _ = mirror_style.set_property(property, &value);
+    }
+    for (property, value) in &[
+        ("position", "absolute"),
+        ("visibility", "hidden"),
+        ("top", "0"),
+        ("left", "0"),
+        ("white-space", "pre-wrap"),
+        ("word-wrap", "break-word"),
+    ] {
+        let _ = mirror_style.set_property(property, value);
+    }
+    mirror.set_text_content(Some(before_caret));
+    let marker = document
+        .create_element("span")
+        .ok()?
+        .dyn_into::<HtmlElement>()
+        .ok()?;
+    // a zero width space gives the marker the height of a line without moving it
+    marker.set_text_content(Some("\u{200b}"));
+    mirror.append_child(&marker).ok()?;
+
+    body.append_child(&mirror).ok()?;
+    let top = marker.offset_top() + marker.offset_height() - element.scroll_top();
+    let left = marker.offset_left() - element.scroll_left();
+    let _ = body.remove_child(&mirror);
+
+    let rect = element.get_bounding_client_rect();
+    Some((rect.top() + f64::from(top), rect.left() + f64::from(left)))
+}
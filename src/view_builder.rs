@@ -1,6 +1,83 @@
-use super::{default_suggestion_view, view, Model};
+use super::{
+    default_suggestion_view, highlight_matches, view, Model, SuggestionContext, SuggestionItem,
+};
 use seed::prelude::*;
-use seed::{style, Attrs, Style};
+use seed::{div, span, Attrs, Style, C, IF};
+
+/// The stylesheet matching the default `ClassNames`, for use with `ViewBuilder::without_inline_styles`
+pub const DEFAULT_STYLESHEET: &str = include_str!("../style/autocomplete.css");
+
+/// Classes added to the elements of the component, for styling it with CSS
+#[derive(Debug, Clone, Copy)]
+pub struct ClassNames {
+    /// The element wrapping the whole component
+    pub wrapper: &'static str,
+    pub input: &'static str,
+    pub clear_button: &'static str,
+    /// The button which opens and closes the menu, see `Model::with_select_mode`
+    pub toggle_button: &'static str,
+    /// The suggestion menu
+    pub menu: &'static str,
+    /// Every suggestion in the menu
+    pub item: &'static str,
+    /// The suggestion highlighted with the keyboard or mouse
+    pub item_highlighted: &'static str,
+    /// The suggestion which is the current selection
+    pub item_selected: &'static str,
+    /// Suggestions which can't be selected
+    pub item_disabled: &'static str,
+    /// Suggestions which have children, see `Model::with_children`
+    pub item_expandable: &'static str,
+    /// The header shown above a group of suggestions
+    pub group_header: &'static str,
+    /// The path to the suggestions shown, when they are the children of another suggestion
+    pub breadcrumb: &'static str,
+    /// The visually hidden region whose text is announced by screen readers
+    pub live_region: &'static str,
+    /// Rows in the menu which aren't suggestions, but tell the user something
+    pub status: &'static str,
+    /// The marks at the end of a suggestion in the default view, a ✓ for the selection and a › for suggestions with children
+    pub marker: &'static str,
+}
+
+impl Default for ClassNames {
+    fn default() -> Self {
+        Self {
+            wrapper: "autocomplete",
+            input: "autocomplete__input",
+            clear_button: "autocomplete__clear",
+            toggle_button: "autocomplete__toggle",
+            menu: "autocomplete__menu",
+            item: "autocomplete__item",
+            item_highlighted: "autocomplete__item--highlighted",
+            item_selected: "autocomplete__item--selected",
+            item_disabled: "autocomplete__item--disabled",
+            item_expandable: "autocomplete__item--expandable",
+            group_header: "autocomplete__group-header",
+            breadcrumb: "autocomplete__breadcrumb",
+            live_region: "autocomplete__live-region",
+            status: "autocomplete__status",
+            marker: "autocomplete__marker",
+        }
+    }
+}
+
+/// The options set on a `ViewBuilder` which apply to any suggestion view
+pub(crate) struct ViewOptions<'m, Ms> {
+    pub(crate) input_attrs: Attrs,
+    /// Styles added to the menu on top of the default inline styles
+    pub(crate) menu_style: Style,
+    pub(crate) clear_button: bool,
+    pub(crate) class_names: ClassNames,
+    pub(crate) inline_styles: bool,
+    /// Render a `textarea` instead of an `input`
+    pub(crate) textarea: bool,
+    /// Render the menu closed whatever the state of the model
+    pub(crate) menu_closed: bool,
+    pub(crate) wrapper_view: Option<Box<dyn Fn(Vec<Node<Ms>>) -> Node<Ms> + 'm>>,
+    pub(crate) input_view: Option<Box<dyn Fn(Node<Ms>) -> Node<Ms> + 'm>>,
+    pub(crate) menu_view: Option<Box<dyn Fn(Vec<Node<Ms>>) -> Node<Ms> + 'm>>,
+}
 
 /// Build up a view of the autocomplete component
 pub trait ViewBuilder<'m, Ms: 'static, Suggestion: 'm>: Sized {
@@ -9,37 +86,85 @@ pub trait ViewBuilder<'m, Ms: 'static, Suggestion: 'm>: Sized {
     fn into_default(self) -> ViewBuilderDefault<'m, Ms, Suggestion>;
 
     fn new(model: &'m Model<Ms, Suggestion>) -> ViewBuilderDefault<'m, Ms, Suggestion> {
-        let menu_style = style! {
-          St::BorderRadius => "3px",
-          St::BoxShadow => "0 2px 12px rgba(0, 0, 0, 0.1)",
-          St::Background => "rgba(255, 255, 255, 0.9)",
-          St::Padding => "2px 0",
-          St::FontSize => "90%",
-          St::Position => "fixed",
-          St::Overflow => "auto",
-        //   St::MaxHeight => "50%", // TODO: don't cheat, let it flow to the bottom
-        };
         ViewBuilderDefault {
             model,
-            input_attrs: Attrs::empty(),
-            menu_style,
+            options: ViewOptions {
+                input_attrs: Attrs::empty(),
+                menu_style: Style::empty(),
+                clear_button: false,
+                class_names: ClassNames::default(),
+                inline_styles: true,
+                textarea: false,
+                menu_closed: false,
+                wrapper_view: None,
+                input_view: None,
+                menu_view: None,
+            },
         }
     }
 
     /// change the input attributes
     fn with_input_attrs(mut self, input_attrs: Attrs) -> Self {
-        self.borrow_default().input_attrs = input_attrs;
+        self.borrow_default().options.input_attrs = input_attrs;
         self
     }
 
     /// add more styles to the menu
     fn add_menu_style(mut self, menu_style: Style) -> Self {
-        self.borrow_default().menu_style.merge(menu_style);
+        self.borrow_default().options.menu_style.merge(menu_style);
+        self
+    }
+
+    /// show a button in the input for clearing it
+    fn with_clear_button(mut self) -> Self {
+        self.borrow_default().options.clear_button = true;
+        self
+    }
+
+    /// change the classes added to the elements of the component
+    fn with_class_names(mut self, class_names: ClassNames) -> Self {
+        self.borrow_default().options.class_names = class_names;
+        self
+    }
+
+    /// don't add any of the default inline styles, leaving all the styling to CSS, see `DEFAULT_STYLESHEET`
+    fn without_inline_styles(mut self) -> Self {
+        self.borrow_default().options.inline_styles = false;
+        self
+    }
+
+    /// render a `textarea` rather than an `input`, for entering text over multiple lines, see `Model::with_trigger`
+    fn with_textarea(mut self) -> Self {
+        self.borrow_default().options.textarea = true;
+        self
+    }
+
+    /// always render the menu closed, for pre-rendering the component to HTML before it is running in the browser
+    fn with_menu_closed(mut self) -> Self {
+        self.borrow_default().options.menu_closed = true;
+        self
+    }
+
+    /// render the element wrapping the component around its children (the input, clear button and menu), the wrapper's classes and styles are added to the returned node
+    fn with_wrapper_view(mut self, wrapper_view: impl Fn(Vec<Node<Ms>>) -> Node<Ms> + 'm) -> Self {
+        self.borrow_default().options.wrapper_view = Some(Box::new(wrapper_view));
+        self
+    }
+
+    /// change or wrap the input, eg. to add a prefix icon, the given input has the component's event handlers and element reference so it must stay in the returned node
+    fn with_input_view(mut self, input_view: impl Fn(Node<Ms>) -> Node<Ms> + 'm) -> Self {
+        self.borrow_default().options.input_view = Some(Box::new(input_view));
+        self
+    }
+
+    /// render the menu around the suggestion rows, eg. to add a header or footer, the menu's classes, styles and event handlers are added to the returned node
+    fn with_menu_view(mut self, menu_view: impl Fn(Vec<Node<Ms>>) -> Node<Ms> + 'm) -> Self {
+        self.borrow_default().options.menu_view = Some(Box::new(menu_view));
         self
     }
 
     /// set the view function for rendering the suggestions
-    fn with_suggestion_view<SuggestionView: Fn(&Suggestion, bool) -> Node<Ms>>(
+    fn with_suggestion_view<SuggestionView: Fn(&Suggestion, &SuggestionContext) -> Node<Ms>>(
         self,
         suggestion_view: SuggestionView,
     ) -> ViewBuilderWithSuggestionView<'m, Ms, Suggestion, SuggestionView> {
@@ -51,11 +176,10 @@ pub trait ViewBuilder<'m, Ms: 'static, Suggestion: 'm>: Sized {
 }
 
 /// Builds a view that uses the default suggestion view function
-/// The default view function requires that the Suggestion implements `ToString`
+/// The default view function shows the display text of each suggestion, see `SuggestionItem`
 pub struct ViewBuilderDefault<'m, Ms, Suggestion> {
     model: &'m Model<Ms, Suggestion>,
-    input_attrs: Attrs,
-    menu_style: Style,
+    options: ViewOptions<'m, Ms>,
 }
 
 impl<'m, Ms: 'static, Suggestion> ViewBuilder<'m, Ms, Suggestion>
@@ -70,16 +194,26 @@ impl<'m, Ms: 'static, Suggestion> ViewBuilder<'m, Ms, Suggestion>
     }
 }
 
-impl<'m, Ms: 'static, Suggestion: ToString> IntoNodes<Ms>
+impl<'m, Ms: 'static, Suggestion: SuggestionItem> IntoNodes<Ms>
     for ViewBuilderDefault<'m, Ms, Suggestion>
 {
     fn into_nodes(self) -> Vec<Node<Ms>> {
-        let ViewBuilderDefault {
-            model,
-            input_attrs,
-            menu_style,
-        } = self;
-        view(&model, default_suggestion_view, input_attrs, menu_style)
+        let ViewBuilderDefault { model, options } = self;
+        if options.inline_styles {
+            view(&model, default_suggestion_view, options)
+        } else {
+            let marker = options.class_names.marker;
+            view(
+                &model,
+                |suggestion: &Suggestion, context: &SuggestionContext| {
+                    div![
+                        highlight_matches(&suggestion.display_text(), context.match_ranges),
+                        IF!(context.is_selected => span![C![marker], "✓"]),
+                    ]
+                },
+                options,
+            )
+        }
     }
 }
 
@@ -101,20 +235,19 @@ impl<'m, Ms: 'static, Suggestion, SuggestionView> ViewBuilder<'m, Ms, Suggestion
     }
 }
 
-impl<'m, Ms: 'static, Suggestion, SuggestionView: Fn(&Suggestion, bool) -> Node<Ms>> IntoNodes<Ms>
-    for ViewBuilderWithSuggestionView<'m, Ms, Suggestion, SuggestionView>
+impl<
+        'm,
+        Ms: 'static,
+        Suggestion: SuggestionItem,
+        SuggestionView: Fn(&Suggestion, &SuggestionContext) -> Node<Ms>,
+    > IntoNodes<Ms> for ViewBuilderWithSuggestionView<'m, Ms, Suggestion, SuggestionView>
 {
     fn into_nodes(self) -> Vec<Node<Ms>> {
         let ViewBuilderWithSuggestionView {
-            view_builder:
-                ViewBuilderDefault {
-                    model,
-                    input_attrs,
-                    menu_style,
-                },
+            view_builder: ViewBuilderDefault { model, options },
             suggestion_view,
         } = self;
 
-        view(&model, suggestion_view, input_attrs, menu_style)
+        view(&model, suggestion_view, options)
     }
 }